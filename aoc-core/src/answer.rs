@@ -0,0 +1,225 @@
+//! Structured results returned by the solvers.
+//!
+//! Solvers don't know or care how their answers get presented, that's up to the pages layer.
+
+use core::fmt;
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use serde::{Serialize, Serializer};
+
+use crate::mem::{try_string, try_vec};
+
+/// A single part's answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Int(i64),
+    UInt(u64),
+    Str(String),
+    /// Rows of characters separated by `\n`, for the days where the answer has to be read off a
+    /// picture.
+    Grid(String),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(v) => write!(f, "{v}"),
+            Value::UInt(v) => write!(f, "{v}"),
+            Value::Str(v) | Value::Grid(v) => f.write_str(v),
+        }
+    }
+}
+
+impl Value {
+    /// `clone`, but running out of memory is an error.
+    pub fn try_clone(&self) -> crate::Result<Self> {
+        Ok(match self {
+            Value::Str(v) => Value::Str(try_string(v)?),
+            Value::Grid(v) => Value::Grid(try_string(v)?),
+            v => v.clone(),
+        })
+    }
+}
+
+/// Numbers stay numbers, everything else is a string.
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error> {
+        match self {
+            Value::Int(v) => serializer.serialize_i64(*v),
+            Value::UInt(v) => serializer.serialize_u64(*v),
+            Value::Str(v) | Value::Grid(v) => serializer.serialize_str(v),
+        }
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Self::Int(value.into())
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<u32> for Value {
+    fn from(value: u32) -> Self {
+        Self::UInt(value.into())
+    }
+}
+
+impl From<u64> for Value {
+    fn from(value: u64) -> Self {
+        Self::UInt(value)
+    }
+}
+
+impl From<usize> for Value {
+    fn from(value: usize) -> Self {
+        Self::UInt(value as u64)
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Self::Str(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Self::Str(value.into())
+    }
+}
+
+/// Everything a solver has to say about its input.
+#[derive(Debug, Default, Clone)]
+pub struct Answer {
+    pub part1: Option<Value>,
+    pub part2: Option<Value>,
+    /// Free-form remarks, e.g. intermediate results worth knowing about.
+    pub notes: Vec<String>,
+}
+
+/// Plain text version of what the pages show, for the host tools.
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (part, value) in [(1, &self.part1), (2, &self.part2)] {
+            match value {
+                Some(Value::Grid(grid)) => writeln!(f, "Part {part}:\n{grid}")?,
+                Some(v) => writeln!(f, "Part {part}: {v}")?,
+                None => writeln!(f, "Part {part}: no answer")?,
+            }
+        }
+        for note in &self.notes {
+            writeln!(f, "{note}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Answer {
+    /// Create an `Answer` with nothing in it
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `clone`, but running out of memory is an error. For copies made once the solve is over,
+    /// when the heap may have little left.
+    pub fn try_clone(&self) -> crate::Result<Self> {
+        let mut notes = try_vec(self.notes.len())?;
+        for note in &self.notes {
+            notes.push(try_string(note)?);
+        }
+        Ok(Self {
+            part1: self.part1.as_ref().map(Value::try_clone).transpose()?,
+            part2: self.part2.as_ref().map(Value::try_clone).transpose()?,
+            notes,
+        })
+    }
+
+    #[must_use]
+    pub fn with_part1(mut self, value: impl Into<Value>) -> Self {
+        self.part1 = Some(value.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_part2(mut self, value: impl Into<Value>) -> Self {
+        self.part2 = Some(value.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+}
+
+/// How one part's answer compared with what it was expected to be.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Check {
+    pub expected: String,
+    pub pass: bool,
+}
+
+impl Check {
+    /// Compare `actual` with `expected`, as text. Whitespace around `expected` doesn't matter, and
+    /// no answer at all is a fail.
+    pub fn new(expected: &str, actual: Option<&Value>) -> Self {
+        let expected = expected.trim();
+        Self {
+            expected: expected.into(),
+            pass: actual.is_some_and(|actual| actual.to_string() == expected),
+        }
+    }
+}
+
+/// Checks for both parts, where an answer was expected.
+#[derive(Debug, Clone, Default)]
+pub struct Checks {
+    pub part1: Option<Check>,
+    pub part2: Option<Check>,
+}
+
+impl Checks {
+    pub fn new(answer: &Answer, expect1: Option<&str>, expect2: Option<&str>) -> Self {
+        Self {
+            part1: expect1.map(|expected| Check::new(expected, answer.part1.as_ref())),
+            part2: expect2.map(|expected| Check::new(expected, answer.part2.as_ref())),
+        }
+    }
+
+    /// Whether every part that had an expected answer got it. True if nothing was checked.
+    pub fn passed(&self) -> bool {
+        [&self.part1, &self.part2].into_iter().flatten().all(|check| check.pass)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks() {
+        let answer = Answer::new().with_part1(11u32).with_part2("co,de");
+        let checks = Checks::new(&answer, Some(" 11\n"), Some("co,de"));
+        assert_eq!(checks.part1, Some(Check { expected: "11".into(), pass: true }));
+        assert!(checks.passed());
+
+        let checks = Checks::new(&answer, Some("12"), None);
+        assert_eq!(checks.part1.as_ref().map(|check| check.pass), Some(false));
+        assert_eq!(checks.part2, None);
+        assert!(!checks.passed());
+
+        let checks = Checks::new(&Answer::new(), None, Some("31"));
+        assert!(!checks.passed());
+        assert!(Checks::default().passed());
+    }
+}
@@ -0,0 +1,140 @@
+//! The web app itself: the router, and the server settings that go with it.
+//!
+//! Lives here rather than in the firmware so the desktop simulator serves exactly the same thing,
+//! with the same limits.
+
+use picoserve::{
+    io::Read,
+    request::RequestParts,
+    response::{Body, Connection, Directory, File, HeadersIter, Response, ResponseWriter},
+    routing::{get_service, parse_path_segment, post_service, Layer, Next, PathRouter},
+    ResponseSent, Router, Timeouts,
+};
+
+use crate::{
+    metrics::Family,
+    pages::{Api, Cache, ClearCache, History, Index, Input, Metrics, RunSaved, SelfTest, Solver},
+};
+
+/// How many connections are served at once. Each one gets its own set of the buffers below.
+pub const HTTP_SERVER_TASKS: usize = 1;
+/// Size of each connection's TCP receive buffer.
+pub const TCP_RX_BUFFER_SIZE: usize = 1024;
+/// Size of each connection's TCP transmit buffer.
+pub const TCP_TX_BUFFER_SIZE: usize = 1024;
+/// Buffer picoserve reads the request line and headers into. Anything that doesn't fit is rejected.
+pub const HTTP_BUFFER_SIZE: usize = 2048;
+
+/// Build the picoserve router. Defines http paths and the corresponding handlers.
+/// Also defines the static content for the server (favicon, "stylesheet" if you can call it that.)
+pub fn make_app() -> Router<impl PathRouter> {
+    let a = Router::new()
+        .route("/", get_service(Index))
+        .route("/selftest", get_service(SelfTest))
+        .route("/cache", get_service(Cache).post_service(ClearCache))
+        .route("/history", get_service(History))
+        .route("/metrics", get_service(Metrics))
+        .route(
+            ("/day", parse_path_segment::<u32>()),
+            get_service(Input)
+                    .post_service(Solver)
+        )
+        .route(("/api/day", parse_path_segment::<u32>()), post_service(Api))
+        .route(("/saved", parse_path_segment::<u32>()), post_service(RunSaved))
+        .nest_service(
+            "/static",
+            const {
+                Directory {
+                    files: &[
+                        ("index.css", File::css(include_str!("static/index.css"))),
+                        ("icon.png", File::with_content_type("image/png", include_bytes!("static/icon.png"))),
+                    ],
+                    ..Directory::DEFAULT
+                }
+            }
+        )
+        .layer(CountRequests);
+    info!("{}", core::any::type_name_of_val(&a));
+    a
+}
+
+/// Requests by route and the status they were answered with. A route for each of the paths
+/// above and one for everything else, by half a dozen statuses or so.
+static REQUESTS: Family<48> =
+    Family::counter("aoc_http_requests_total", "HTTP requests, by route and status.");
+
+/// The route a path was served by, with any day in it left out, to keep the number of series down.
+fn route(path: &str) -> &'static str {
+    const ROUTES: &[(&str, &str)] = &[
+        ("/day/", "/day/{n}"),
+        ("/api/day/", "/api/day/{n}"),
+        ("/saved/", "/saved/{n}"),
+        ("/static/", "/static"),
+    ];
+    match path {
+        "/" => "/",
+        "/selftest" => "/selftest",
+        "/cache" => "/cache",
+        "/history" => "/history",
+        "/metrics" => "/metrics",
+        _ => ROUTES
+            .iter()
+            .find(|(prefix, _)| path.starts_with(prefix))
+            .map_or("other", |(_, route)| route),
+    }
+}
+
+/// Counts every request in [`REQUESTS`] as its response goes out.
+struct CountRequests;
+
+impl<State, PathParameters> Layer<State, PathParameters> for CountRequests {
+    type NextState = State;
+    type NextPathParameters = PathParameters;
+
+    async fn call_layer<
+        'a,
+        R: Read + 'a,
+        NextLayer: Next<'a, R, Self::NextState, Self::NextPathParameters>,
+        W: ResponseWriter<Error = R::Error>,
+    >(
+        &self,
+        next: NextLayer,
+        state: &State,
+        path_parameters: PathParameters,
+        request_parts: RequestParts<'_>,
+        response_writer: W,
+    ) -> Result<ResponseSent, W::Error> {
+        let route = route(request_parts.path().encoded());
+        next.run(state, path_parameters, Counted { route, inner: response_writer }).await
+    }
+}
+
+struct Counted<W> {
+    route: &'static str,
+    inner: W,
+}
+
+impl<W: ResponseWriter> ResponseWriter for Counted<W> {
+    type Error = W::Error;
+
+    async fn write_response<R: Read<Error = Self::Error>, H: HeadersIter, B: Body>(
+        self,
+        connection: Connection<'_, R>,
+        response: Response<H, B>,
+    ) -> Result<ResponseSent, Self::Error> {
+        let status = response.status_code().as_u16();
+        REQUESTS.inc(format_args!(r#"route="{}",status="{status}""#, self.route));
+        self.inner.write_response(connection, response).await
+    }
+}
+
+/// Server configuration, shared by the firmware and the simulator.
+/// picoserve's duration type depends on the runtime, so the caller says how to make one.
+pub fn make_config<D>(from_secs: fn(u64) -> D) -> picoserve::Config<D> {
+    picoserve::Config::new(Timeouts {
+        start_read_request: Some(from_secs(5)),
+        read_request: Some(from_secs(1)),
+        write: Some(from_secs(1)),
+    })
+    .keep_connection_alive()
+}
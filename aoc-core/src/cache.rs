@@ -0,0 +1,230 @@
+//! Answers to inputs that have been solved before, so that posting the same input again doesn't
+//! mean solving it again.
+//!
+//! Entries are keyed on the day, the parts asked for and a hash of the input. The hash is worked
+//! out by [`crate::helpers::InputReader`] as the input goes through it. Only a handful of answers
+//! are kept, the oldest goes when there's no room for a new one.
+
+use core::cell::RefCell;
+
+use critical_section::Mutex;
+use embassy_time::Duration;
+use portable_atomic::{AtomicU32, Ordering};
+use serde::Deserialize;
+
+use crate::{
+    answer::Answer,
+    mem::HeapUsage,
+    problems::Parts,
+    stack::StackUsage,
+    timing::Timings,
+};
+
+/// How many answers are kept.
+pub const CACHE_CAPACITY: usize = 8;
+
+/// 64 bit FNV-1a. Not much of a hash, but it's cheap and it goes a byte at a time, so it can keep
+/// up with the input however it's split into reads.
+#[derive(Clone, Copy, Debug)]
+pub struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Fnv1a {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    pub const fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 ^ u64::from(b)).wrapping_mul(Self::PRIME);
+        }
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Whether a solve may use the cache.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum CacheMode {
+    /// Solve as the input arrives and file the answer afterwards. The input's never held in memory,
+    /// so this can't look anything up, there's no hash until the solve is over.
+    #[default]
+    #[serde(rename = "on")]
+    On,
+    /// Read the whole input in first, and answer from the cache if it's been solved before.
+    #[serde(rename = "lookup")]
+    Lookup,
+    /// Solve from scratch and leave the cache alone, e.g. to time the solver. Nor is the input
+    /// saved, as the input page's examples rely on.
+    #[serde(rename = "off")]
+    Off,
+}
+
+/// What an answer is filed under.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Key {
+    pub day: u32,
+    pub parts: Parts,
+    /// [`Fnv1a`] of the input.
+    pub hash: u64,
+}
+
+/// A solve as it first happened. The figures are from then, not from the lookup.
+#[derive(Clone, Debug)]
+pub struct Cached {
+    pub answer: Answer,
+    pub elapsed: Duration,
+    pub heap: HeapUsage,
+    pub stack: Option<StackUsage>,
+    pub timings: Timings,
+}
+
+struct Entry {
+    key: Key,
+    cached: Cached,
+    hits: u32,
+}
+
+/// What's in the cache, for the cache page.
+#[derive(Clone, Copy, Debug)]
+pub struct Summary {
+    pub key: Key,
+    pub elapsed: Duration,
+    pub hits: u32,
+}
+
+/// Oldest first.
+static CACHE: Mutex<RefCell<heapless::Vec<Entry, CACHE_CAPACITY>>> =
+    Mutex::new(RefCell::new(heapless::Vec::new()));
+static HITS: AtomicU32 = AtomicU32::new(0);
+static MISSES: AtomicU32 = AtomicU32::new(0);
+
+/// Look up the answer filed under `key`.
+pub fn get(key: &Key) -> Option<Cached> {
+    let found = critical_section::with(|cs| {
+        let mut cache = CACHE.borrow_ref_mut(cs);
+        let entry = cache.iter_mut().find(|entry| entry.key == *key)?;
+        // No room for a copy is as good as a miss, the input's still there to solve
+        let Cached { answer, elapsed, heap, stack, timings } = &entry.cached;
+        let answer = answer.try_clone().ok()?;
+        entry.hits += 1;
+        Some(Cached { answer, elapsed: *elapsed, heap: *heap, stack: *stack, timings: timings.clone() })
+    });
+    let counter = if found.is_some() { &HITS } else { &MISSES };
+    counter.fetch_add(1, Ordering::Relaxed);
+    found
+}
+
+/// File `cached` under `key`, making room if need be.
+pub fn insert(key: Key, cached: Cached) {
+    // Dropping the old entry can take a while for big answers, keep it out of the critical section
+    let evicted = critical_section::with(|cs| {
+        let mut cache = CACHE.borrow_ref_mut(cs);
+        let evicted = match cache.iter().position(|entry| entry.key == key) {
+            Some(index) => Some(cache.remove(index)),
+            None if cache.is_full() => Some(cache.remove(0)),
+            None => None,
+        };
+        // There's always room by now
+        let _ = cache.push(Entry { key, cached, hits: 0 });
+        evicted
+    });
+    drop(evicted);
+}
+
+/// Empty the cache. Returns how many answers were thrown away.
+pub fn clear() -> usize {
+    let cleared = critical_section::with(|cs| core::mem::take(&mut *CACHE.borrow_ref_mut(cs)));
+    cleared.len()
+}
+
+/// Every answer in the cache, oldest first.
+pub fn summaries() -> heapless::Vec<Summary, CACHE_CAPACITY> {
+    critical_section::with(|cs| {
+        CACHE
+            .borrow_ref(cs)
+            .iter()
+            .map(|entry| Summary { key: entry.key, elapsed: entry.cached.elapsed, hits: entry.hits })
+            .collect()
+    })
+}
+
+/// Lookups that found an answer, and lookups that didn't.
+pub fn hits_and_misses() -> (u32, u32) {
+    (HITS.load(Ordering::Relaxed), MISSES.load(Ordering::Relaxed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(day: u32, input: &str) -> Key {
+        let mut hash = Fnv1a::new();
+        hash.update(input.as_bytes());
+        Key { day, parts: Parts::Both, hash: hash.finish() }
+    }
+
+    fn cached(part1: u32) -> Cached {
+        Cached {
+            answer: Answer::new().with_part1(part1),
+            elapsed: Duration::from_millis(u64::from(part1)),
+            heap: HeapUsage::default(),
+            stack: None,
+            timings: Timings::new(),
+        }
+    }
+
+    #[test]
+    fn fnv1a() {
+        // Reference values for the 64 bit FNV-1a
+        assert_eq!(Fnv1a::new().finish(), 0xcbf2_9ce4_8422_2325);
+        let mut hash = Fnv1a::new();
+        hash.update(b"a");
+        assert_eq!(hash.finish(), 0xaf63_dc4c_8601_ec8c);
+        // Doesn't matter how it's split up
+        let mut split = Fnv1a::new();
+        split.update(b"foo");
+        split.update(b"bar");
+        let mut whole = Fnv1a::new();
+        whole.update(b"foobar");
+        assert_eq!(split.finish(), whole.finish());
+        assert_eq!(whole.finish(), 0x8594_4171_f739_67e8);
+    }
+
+    // The cache is global, so everything that touches it is in the one test
+    #[test]
+    fn lookups() {
+        clear();
+        assert!(get(&key(1, "1 2")).is_none());
+        insert(key(1, "1 2"), cached(7));
+        let hit = get(&key(1, "1 2")).unwrap();
+        assert_eq!(hit.elapsed, Duration::from_millis(7));
+        assert!(get(&key(2, "1 2")).is_none());
+        assert!(get(&Key { parts: Parts::One, ..key(1, "1 2") }).is_none());
+
+        // Refiling replaces the old answer
+        insert(key(1, "1 2"), cached(8));
+        assert_eq!(summaries().len(), 1);
+
+        for n in 0..CACHE_CAPACITY {
+            insert(key(3, &alloc::format!("{n}")), cached(1));
+        }
+        let summaries = summaries();
+        assert_eq!(summaries.len(), CACHE_CAPACITY);
+        assert!(summaries.iter().all(|summary| summary.key.day == 3));
+        assert!(get(&key(1, "1 2")).is_none());
+
+        assert_eq!(clear(), CACHE_CAPACITY);
+        assert!(get(&key(3, "0")).is_none());
+    }
+}
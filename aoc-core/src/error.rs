@@ -0,0 +1,119 @@
+use picoserve::response::StatusCode;
+
+/// Crate-scoped error type that allows converging error types through ?
+#[derive(Debug, thiserror::Error)]
+pub enum AerError {
+    #[error("Format error (format body probably too long)")]
+    FmtError(core::fmt::Error),
+    #[error("Day {day} doesn't exist")]
+    BadDay { day: u32 },
+    #[error("Day {day} hasn't been implemented yet")]
+    Unimplemented { day: u32 },
+    #[error("Picoserve IO Error: {0:?}")]
+    PicoserveIo(picoserve::io::ErrorKind),
+    #[error("Input size error: {message}. Expected {expected}, got {got}")]
+    InputSize{expected: usize, got: usize, message: &'static str},
+    #[error("Input is {size} bytes, over the {limit} byte limit for this day")]
+    InputTooLarge { size: usize, limit: usize },
+    #[error("Input too large for device memory (needed {needed} bytes, {free} bytes free)")]
+    OutOfMemory { needed: usize, free: usize },
+    #[error("No puzzle input in the request body")]
+    MissingMessage,
+    #[error("Malformed or invalid utf-8 in input: {0}")]
+    Utf8(#[from] core::str::Utf8Error),
+    #[error("Malformed or invalid utf-8 in input: {0}")]
+    AllocUtf8(#[from] alloc::string::FromUtf8Error),
+    #[error("Integer parse error: {0}")]
+    IntParse(#[from] core::num::ParseIntError),
+    #[error("Exact read error")]
+    ExactRead,
+    #[error("Couldn't make sense of the options in the query string or form")]
+    BadQuery,
+    #[error("Can't benchmark {runs} runs, it has to be between 1 and {max}")]
+    BenchRuns { runs: u32, max: u32 },
+    #[error("Flash storage error: {0:?}")]
+    Flash(crate::storage::FlashError),
+    #[error("There's no saved input for day {day}")]
+    NothingSaved { day: u32 },
+}
+
+impl AerError {
+    /// Short, stable identifier for the error variant, for machine readable responses.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AerError::FmtError(_) => "format",
+            AerError::BadDay { .. } => "bad_day",
+            AerError::Unimplemented { .. } => "unimplemented",
+            AerError::PicoserveIo(_) => "io",
+            AerError::InputSize { .. } => "input_size",
+            AerError::InputTooLarge { .. } => "input_too_large",
+            AerError::OutOfMemory { .. } => "out_of_memory",
+            AerError::MissingMessage => "missing_message",
+            AerError::Utf8(_) | AerError::AllocUtf8(_) => "utf8",
+            AerError::IntParse(_) => "int_parse",
+            AerError::ExactRead => "exact_read",
+            AerError::BadQuery => "bad_query",
+            AerError::BenchRuns { .. } => "bench_runs",
+            AerError::Flash(_) => "flash",
+            AerError::NothingSaved { .. } => "nothing_saved",
+        }
+    }
+
+    /// The HTTP status to report this error with.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AerError::BadDay { .. } | AerError::NothingSaved { .. } => StatusCode::NOT_FOUND,
+            AerError::Unimplemented { .. } => StatusCode::NOT_IMPLEMENTED,
+            AerError::InputSize { .. }
+            | AerError::InputTooLarge { .. }
+            | AerError::OutOfMemory { .. } => {
+                StatusCode::PAYLOAD_TOO_LARGE
+            },
+            AerError::MissingMessage
+            | AerError::Utf8(_)
+            | AerError::AllocUtf8(_)
+            | AerError::IntParse(_)
+            | AerError::ExactRead
+            | AerError::BadQuery
+            | AerError::BenchRuns { .. } => StatusCode::BAD_REQUEST,
+            AerError::FmtError(_) | AerError::PicoserveIo(_) | AerError::Flash(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            },
+        }
+    }
+}
+
+/// Some constraints on trait scope means that some error types need manual conversion
+/// Unfortunately this trait is required relatively regularly for `picoserve::io::Error`
+pub trait IntoAer<T> {
+    fn into_aer(self) -> Result<T>
+    where
+        T: Sized;
+}
+
+impl<T, E> IntoAer<T> for core::result::Result<T, E>
+where
+    E: picoserve::io::Error,
+{
+    fn into_aer(self) -> Result<T> {
+        self.map_err(|e| AerError::PicoserveIo(e.kind()))
+    }
+}
+
+/// Can't use the thiserror #[from] marker for this, have to do it manually
+impl From<core::fmt::Error> for AerError {
+    fn from(value: core::fmt::Error) -> Self {
+        Self::FmtError(value)
+    }
+}
+
+/// Allows us to log errors straight to the serial port.
+#[cfg(feature = "defmt")]
+impl defmt::Format for AerError {
+    fn format(&self, fmt: defmt::Formatter) {
+        defmt::write!(fmt, "{:?}", defmt::Debug2Format(self));
+    }
+}
+
+/// Crate-level result type.
+pub type Result<T> = core::result::Result<T, AerError>;
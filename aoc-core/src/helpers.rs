@@ -0,0 +1,761 @@
+use core::fmt::Write;
+
+use alloc::{string::String, vec::Vec};
+use picoserve::{
+    io::{ErrorType, Read},
+    url_encoded::UrlEncodedString,
+};
+
+use crate::{
+    cache::Fnv1a,
+    error::{AerError, IntoAer},
+    mem::try_vec,
+};
+
+const PREFIX: &[u8] = b"message=";
+
+/// Size of the buffer the body is read through. Any form fields ahead of the input have to fit in
+/// it, along with the `message=` prefix.
+const BUF_SIZE: usize = 128;
+
+/// Room for form fields, once they're percent-encoded.
+const FIELDS_CAPACITY: usize = 192;
+
+/// The fields the input page's forms send ahead of the input.
+const PAGE_FIELDS: &[&[u8]] = &[b"part", b"expect1", b"expect2", b"cache"];
+
+/// How the puzzle input is wrapped up in the request body, going by the `Content-Type` header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum InputFormat {
+    /// `text/plain`. Posted by the input form as `message=<input>`, or a raw upload from a script.
+    PlainText,
+    /// `application/x-www-form-urlencoded`. A percent-encoded `message=<input>` form body, or a
+    /// raw upload from something like `curl --data-binary` which uses this type by default.
+    UrlEncoded,
+    /// `application/octet-stream`, or no content type at all. The body is the input, as-is.
+    Raw,
+}
+
+impl InputFormat {
+    /// Work out the input format from the value of the `Content-Type` header, if there was one.
+    /// Parameters such as `charset` are ignored, and anything we don't recognise is treated as raw.
+    pub fn from_content_type(content_type: Option<&[u8]>) -> Self {
+        let Some(content_type) = content_type else {
+            return Self::Raw;
+        };
+        let media_type = content_type
+            .split(|b| *b == b';')
+            .next()
+            .unwrap_or_default()
+            .trim_ascii();
+        if media_type.eq_ignore_ascii_case(b"text/plain") {
+            Self::PlainText
+        } else if media_type.eq_ignore_ascii_case(b"application/x-www-form-urlencoded") {
+            Self::UrlEncoded
+        } else {
+            Self::Raw
+        }
+    }
+}
+
+/// Where we are in decoding a `%XX` escape, which can be split across reads.
+#[derive(Clone, Copy)]
+enum Escape {
+    None,
+    Percent,
+    /// Seen the first digit, keeps the digit as received so it can be passed through as-is if the
+    /// escape turns out to be bogus.
+    Hex { digit: u8, value: u8 },
+}
+
+/// The shared reader for puzzle input. Strips the `message=` prefix from form bodies and undoes
+/// percent-encoding, so the solvers only ever see the input itself.
+///
+/// Form bodies are recognised by the prefix, which means raw uploads of plain text work no matter
+/// which of the text content types they were sent with. Other form fields can come before the
+/// input, e.g. `part=1&message=...`, and are kept aside in [`InputReader::form_fields`].
+pub struct InputReader<R> {
+    inner: R,
+    /// Whether to percent-decode what comes out of `inner`.
+    decode: bool,
+    escape: Escape,
+    /// Form fields from ahead of the input, percent-encoded and separated by `&`.
+    fields: heapless::String<FIELDS_CAPACITY>,
+    /// Of the input handed out so far.
+    hash: Fnv1a,
+    /// Of the input handed out so far, if asked for with [`InputReader::keep_copy`].
+    copy: Option<Vec<u8>>,
+    /// Body bytes that have been read from `inner` but not handed out yet
+    buf: [u8; BUF_SIZE],
+    start: usize,
+    end: usize,
+    size_hint: usize,
+    /// Input bytes read so far, including any that went over `limit` and weren't handed out.
+    read_count: usize,
+    /// Most input bytes to hand out, see [`InputReader::limit`].
+    limit: usize,
+    /// Set once there was more input than `limit`.
+    over_limit: bool,
+}
+
+impl<R: Read> InputReader<R> {
+    /// Wrap a request body of `content_length` bytes. This peeks at the start of the body to see
+    /// whether it's a form post.
+    pub async fn new(inner: R, format: InputFormat, content_length: usize) -> crate::Result<Self> {
+        let mut reader = Self {
+            inner,
+            decode: false,
+            escape: Escape::None,
+            fields: heapless::String::new(),
+            hash: Fnv1a::new(),
+            copy: None,
+            buf: [0; BUF_SIZE],
+            start: 0,
+            end: 0,
+            size_hint: content_length,
+            read_count: 0,
+            limit: usize::MAX,
+            over_limit: false,
+        };
+        if format != InputFormat::Raw && reader.read_form_fields(format).await? {
+            info!("Form body, format {}", format);
+            reader.size_hint -= reader.start;
+            reader.decode = format == InputFormat::UrlEncoded;
+        }
+        if reader.start == reader.end {
+            reader.start = 0;
+            reader.end = reader.inner.read(&mut reader.buf).await.into_aer()?;
+        }
+        if reader.end == 0 {
+            error!("Request body has no puzzle input in it");
+            return Err(AerError::MissingMessage);
+        }
+        Ok(reader)
+    }
+
+    /// Upper bound on the number of input bytes this reader will produce.
+    pub fn size_hint(&self) -> usize {
+        self.size_hint
+    }
+
+    /// Turn away inputs over `limit` bytes, not counting any form fields ahead of them. If the input
+    /// is known to be too big already that's an error straight away. A percent-encoded input could
+    /// still decode to less than its size in the body, so it's read until it goes over instead. The
+    /// reader then stops as though that was the end, and [`InputReader::finish`] reports it.
+    pub fn limit(&mut self, limit: usize) -> crate::Result<()> {
+        // Every input byte takes one to three bytes of the body
+        let smallest = if self.decode { self.size_hint.div_ceil(3) } else { self.size_hint };
+        if smallest > limit {
+            return Err(AerError::InputTooLarge { size: smallest, limit });
+        }
+        self.limit = limit;
+        self.size_hint = self.size_hint.min(limit);
+        Ok(())
+    }
+
+    /// Any form fields that came before the input, e.g. `part=1`. Empty for raw uploads.
+    pub fn form_fields(&self) -> UrlEncodedString<'_> {
+        UrlEncodedString(&self.fields)
+    }
+
+    /// [`Fnv1a`] of the input read so far, decoded. Only covers the whole input once it's been
+    /// read to the end.
+    pub fn hash(&self) -> u64 {
+        self.hash.finish()
+    }
+
+    /// Bytes of input read so far, decoded and not counting any form fields. Only covers the whole
+    /// input once it's been read to the end.
+    pub fn input_len(&self) -> usize {
+        self.read_count
+    }
+
+    /// Keep a copy of the input as it goes through, e.g. to save it once it's been solved. Only if
+    /// it's no more than `limit` bytes and there's room for it, otherwise nothing's kept. The
+    /// room's taken up front, so call this before reading anything.
+    pub fn keep_copy(&mut self, limit: usize) {
+        if self.size_hint > limit {
+            return;
+        }
+        match try_vec(self.size_hint) {
+            Ok(copy) => self.copy = Some(copy),
+            Err(_) => warn!("No room to keep a copy of the input, {} bytes", self.size_hint),
+        }
+    }
+
+    /// The copy from [`InputReader::keep_copy`], if one was kept. It only has all of the input once
+    /// it's been read to the end, see [`InputReader::finish`].
+    pub fn take_copy(&mut self) -> Option<Vec<u8>> {
+        self.copy.take()
+    }
+
+    /// Read whatever's left of the input, so that [`InputReader::hash`] covers all of it, and return
+    /// the hash. Solvers don't always read to the end. An input that turned out to be over the
+    /// [`InputReader::limit`] is an error.
+    pub async fn finish(&mut self) -> crate::Result<u64> {
+        let mut rest = [0; 64];
+        while self.read(&mut rest).await.into_aer()? > 0 {}
+        if self.over_limit {
+            // Only read to find out how big it was
+            loop {
+                let n = self.read_decoded(&mut rest).await.into_aer()?;
+                if n == 0 {
+                    break;
+                }
+                self.read_count += n;
+            }
+            error!("Input is {} bytes once decoded, over the {} byte limit", self.read_count, self.limit);
+            return Err(AerError::InputTooLarge { size: self.read_count, limit: self.limit });
+        }
+        Ok(self.hash())
+    }
+
+    /// Look for form fields at the start of the body, up to and including `message=`. If it's a
+    /// form, `start` is moved past them to the input. If not, the body is raw input and everything
+    /// read so far is left in the buffer for the solver.
+    ///
+    /// A form whose fields didn't all fit is a [`AerError::BadQuery`] rather than input, or the
+    /// solver would be handed the fields. Without `message=` in the buffer there's no telling a
+    /// form from input that looks like one, e.g. `p=0,4 v=3,-3`, so it's taken to be a form if it
+    /// only has fields the input page sends.
+    async fn read_form_fields(&mut self, format: InputFormat) -> crate::Result<bool> {
+        // Browsers separate `text/plain` fields with CRLF
+        let separator = if format == InputFormat::UrlEncoded { b'&' } else { b'\n' };
+        let mut pos = 0;
+        let mut dropped = false;
+        let mut page_fields = true;
+        loop {
+            let rest = &self.buf[pos..self.end];
+            if rest.starts_with(PREFIX) {
+                if dropped {
+                    error!("Form fields don't fit in {} bytes", FIELDS_CAPACITY);
+                    return Err(AerError::BadQuery);
+                }
+                self.start = pos + PREFIX.len();
+                return Ok(true);
+            }
+            if let Some(len) = rest.iter().position(|b| *b == separator) {
+                let field = &rest[..len];
+                let field = field.strip_suffix(b"\r").unwrap_or(field);
+                let Some((name, value)) = split_field(field) else {
+                    break;
+                };
+                page_fields &= PAGE_FIELDS.contains(&name);
+                let fields_len = self.fields.len();
+                if push_field(&mut self.fields, name, value, format).is_err() {
+                    warn!("No room to keep form field {}", core::str::from_utf8(name).unwrap_or("?"));
+                    self.fields.truncate(fields_len);
+                    dropped = true;
+                }
+                pos += len + 1;
+            } else if could_be_field(rest) && self.end < self.buf.len() {
+                // The rest of the field, or `message=`, is still on its way
+                let read_size = self.inner.read(&mut self.buf[self.end..]).await.into_aer()?;
+                if read_size == 0 {
+                    break;
+                }
+                self.end += read_size;
+            } else {
+                let too_long = split_field(rest).is_some_and(|(name, _)| PAGE_FIELDS.contains(&name));
+                if page_fields && too_long && self.end == self.buf.len() {
+                    error!("Form field too long for the {} byte buffer", BUF_SIZE);
+                    return Err(AerError::BadQuery);
+                }
+                break;
+            }
+        }
+        self.fields.clear();
+        Ok(false)
+    }
+
+    /// Percent-decode as much of the buffered body as will fit into `out`.
+    fn decode_into(&mut self, out: &mut [u8]) -> usize {
+        let mut written = 0;
+        while self.start < self.end && written < out.len() {
+            let b = self.buf[self.start];
+            match (self.escape, hex_value(b)) {
+                (Escape::None, _) if b == b'%' => self.escape = Escape::Percent,
+                (Escape::None, _) => {
+                    out[written] = if b == b'+' { b' ' } else { b };
+                    written += 1;
+                },
+                (Escape::Percent, Some(value)) => self.escape = Escape::Hex { digit: b, value },
+                (Escape::Hex { value, .. }, Some(lo)) => {
+                    out[written] = (value << 4) | lo;
+                    written += 1;
+                    self.escape = Escape::None;
+                },
+                // Not actually an escape. Pass the '%' (and digit) through and look at this
+                // byte again on the next go round.
+                (Escape::Percent, None) => {
+                    out[written] = b'%';
+                    written += 1;
+                    self.escape = Escape::None;
+                    continue;
+                },
+                (Escape::Hex { digit, .. }, None) => {
+                    if out.len() - written < 2 {
+                        break;
+                    }
+                    out[written] = b'%';
+                    out[written + 1] = digit;
+                    written += 2;
+                    self.escape = Escape::None;
+                    continue;
+                },
+            }
+            self.start += 1;
+        }
+        written
+    }
+}
+
+/// Add a form field to `fields`, escaping it if it came from a `text/plain` form.
+fn push_field(
+    fields: &mut heapless::String<FIELDS_CAPACITY>,
+    name: &[u8],
+    value: &[u8],
+    format: InputFormat,
+) -> Result<(), ()> {
+    if !fields.is_empty() {
+        fields.push('&')?;
+    }
+    for &b in name.iter().chain(b"=").chain(value) {
+        let encoded = format == InputFormat::UrlEncoded && b.is_ascii_graphic();
+        if encoded || b.is_ascii_alphanumeric() || b"=-_.~".contains(&b) {
+            fields.push(char::from(b))?;
+        } else {
+            write!(fields, "%{b:02X}").map_err(|_| ())?;
+        }
+    }
+    Ok(())
+}
+
+fn is_field_name(name: &[u8]) -> bool {
+    name.iter().all(|b| b.is_ascii_alphanumeric() || *b == b'_')
+}
+
+/// Split `name=value`. `None` if it isn't a form field.
+fn split_field(field: &[u8]) -> Option<(&[u8], &[u8])> {
+    let split = field.iter().position(|b| *b == b'=')?;
+    let (name, value) = (&field[..split], &field[split + 1..]);
+    (!name.is_empty() && is_field_name(name)).then_some((name, value))
+}
+
+/// Whether `start` could be the start of a form field, or of `message=`.
+fn could_be_field(start: &[u8]) -> bool {
+    let name = start.split(|b| *b == b'=').next().unwrap_or_default();
+    is_field_name(name)
+}
+
+fn hex_value(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'a'..=b'f' => Some(b - b'a' + 10),
+        b'A'..=b'F' => Some(b - b'A' + 10),
+        _ => None,
+    }
+}
+
+impl<R: Read> ErrorType for InputReader<R> {
+    type Error = R::Error;
+}
+
+impl<R: Read> Read for InputReader<R> {
+    async fn read(&mut self, out: &mut [u8]) -> Result<usize, Self::Error> {
+        if self.over_limit {
+            return Ok(0);
+        }
+        let n = self.read_decoded(out).await?;
+        self.read_count += n;
+        if self.read_count > self.limit {
+            // Leave the solver with what it's had, the caller finds out from `finish`
+            self.over_limit = true;
+            return Ok(0);
+        }
+        self.hash.update(&out[..n]);
+        if let Some(copy) = &mut self.copy {
+            // Can't happen, the size hint is an upper bound. If it does, don't let it grow
+            if copy.len() + n > copy.capacity() {
+                self.copy = None;
+            } else {
+                copy.extend_from_slice(&out[..n]);
+            }
+        }
+        Ok(n)
+    }
+}
+
+impl<R: Read> InputReader<R> {
+    /// The guts of [`Read::read`], less the hashing.
+    async fn read_decoded(&mut self, out: &mut [u8]) -> Result<usize, R::Error> {
+        if out.is_empty() {
+            return Ok(0);
+        }
+        if !self.decode {
+            if self.start < self.end {
+                let n = (self.end - self.start).min(out.len());
+                out[..n].copy_from_slice(&self.buf[self.start..self.start + n]);
+                self.start += n;
+                return Ok(n);
+            }
+            return self.inner.read(out).await;
+        }
+        loop {
+            if self.start == self.end {
+                self.start = 0;
+                self.end = self.inner.read(&mut self.buf).await?;
+                if self.end == 0 {
+                    // A trailing partial escape isn't an escape at all
+                    return Ok(match self.escape {
+                        Escape::None => 0,
+                        Escape::Percent => {
+                            self.escape = Escape::None;
+                            out[0] = b'%';
+                            1
+                        },
+                        Escape::Hex { digit, .. } => {
+                            self.escape = Escape::None;
+                            self.buf[0] = digit;
+                            self.end = 1;
+                            out[0] = b'%';
+                            1
+                        },
+                    });
+                }
+            }
+            // A read that's all escape characters decodes to nothing, but returning 0 means the
+            // end of the body. Go round again instead.
+            let written = self.decode_into(out);
+            if written > 0 {
+                return Ok(written);
+            }
+        }
+    }
+}
+
+/// Helper function for reading the whole of the puzzle input into memory.
+///
+/// `r` is expected to be an [`InputReader`], or something else that produces just the input.
+/// `size_hint` is an upper bound on the size of the input.
+pub async fn read_input<R: Read>(r: &mut R, size_hint: usize) -> crate::Result<String> {
+    let mut input_buf = try_vec(size_hint)?;
+    input_buf.resize(size_hint, 0);
+    let mut read_count: usize = 0;
+
+    while read_count < size_hint {
+        let read_size = r.read(&mut input_buf[read_count..]).await.into_aer()?;
+        if read_size == 0 {
+            break;
+        }
+        read_count += read_size;
+    }
+    input_buf.truncate(read_count);
+    Ok(String::from_utf8(input_buf)?)
+}
+
+/// Reads input a line at a time through a small fixed buffer, so that days which can work line by
+/// line don't need to hold the whole input in memory.
+///
+/// Lines can be split across any number of reads from `inner`. A line longer than the buffer is an
+/// error, `N` needs to be big enough for the longest line of the day's input.
+pub struct LineReader<R, const N: usize = 256> {
+    inner: R,
+    buf: [u8; N],
+    start: usize,
+    end: usize,
+    eof: bool,
+}
+
+impl<R: Read, const N: usize> LineReader<R, N> {
+    /// `inner` should produce just the input, usually it'll be an [`InputReader`].
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            buf: [0; N],
+            start: 0,
+            end: 0,
+            eof: false,
+        }
+    }
+
+    /// Get the next line of input, without its line ending. `None` once the input runs out.
+    pub async fn next_line(&mut self) -> crate::Result<Option<&str>> {
+        let (line_start, line_end) = loop {
+            if let Some(i) = self.buf[self.start..self.end].iter().position(|b| *b == b'\n') {
+                let line = (self.start, self.start + i);
+                self.start += i + 1;
+                break line;
+            }
+            if self.eof {
+                if self.start == self.end {
+                    return Ok(None);
+                }
+                let line = (self.start, self.end);
+                self.start = self.end;
+                break line;
+            }
+            // Partial line, shuffle it to the front and fill up the rest
+            self.buf.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.start = 0;
+            if self.end == N {
+                error!("Input line longer than the {} byte line buffer", N);
+                return Err(AerError::InputSize {
+                    expected: N,
+                    got: self.end,
+                    message: "line too long for the line buffer",
+                });
+            }
+            let read_size = self.inner.read(&mut self.buf[self.end..]).await.into_aer()?;
+            if read_size == 0 {
+                self.eof = true;
+            }
+            self.end += read_size;
+        };
+        let line = &self.buf[line_start..line_end];
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        Ok(Some(core::str::from_utf8(line)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{format, string::String, vec::Vec};
+    use core::future::Future;
+
+    use super::*;
+
+    /// Hands out a body `chunk` bytes at a time, like a slow TCP connection.
+    struct Chunked<'a> {
+        data: &'a [u8],
+        chunk: usize,
+    }
+
+    impl ErrorType for Chunked<'_> {
+        type Error = core::convert::Infallible;
+    }
+
+    impl Read for Chunked<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> impl Future<Output = Result<usize, Self::Error>> {
+            let n = self.chunk.min(buf.len()).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            core::future::ready(Ok(n))
+        }
+    }
+
+    fn read_body(body: &[u8], format: InputFormat, chunk: usize) -> crate::Result<String> {
+        embassy_futures::block_on(async {
+            let mut reader = InputReader::new(Chunked { data: body, chunk }, format, body.len()).await?;
+            let size_hint = reader.size_hint();
+            read_input(&mut reader, size_hint).await
+        })
+    }
+
+    /// The form fields and the input, from a form body.
+    fn read_form(body: &[u8], format: InputFormat, chunk: usize) -> (String, String) {
+        embassy_futures::block_on(async {
+            let mut reader = InputReader::new(Chunked { data: body, chunk }, format, body.len())
+                .await
+                .unwrap();
+            let fields = reader.form_fields().0.into();
+            let size_hint = reader.size_hint();
+            (fields, read_input(&mut reader, size_hint).await.unwrap())
+        })
+    }
+
+    fn read_lines(body: &[u8], chunk: usize) -> Vec<String> {
+        embassy_futures::block_on(async {
+            let mut reader = LineReader::<_, 8>::new(Chunked { data: body, chunk });
+            let mut lines = Vec::new();
+            while let Some(line) = reader.next_line().await.unwrap() {
+                lines.push(line.into());
+            }
+            lines
+        })
+    }
+
+    #[test]
+    fn content_types() {
+        assert_eq!(InputFormat::from_content_type(None), InputFormat::Raw);
+        assert_eq!(InputFormat::from_content_type(Some(b"text/plain")), InputFormat::PlainText);
+        assert_eq!(
+            InputFormat::from_content_type(Some(b"Application/x-www-form-urlencoded; charset=UTF-8")),
+            InputFormat::UrlEncoded
+        );
+        assert_eq!(
+            InputFormat::from_content_type(Some(b"application/octet-stream")),
+            InputFormat::Raw
+        );
+    }
+
+    #[test]
+    fn input_formats() {
+        for chunk in 1..10 {
+            let plain = read_body(b"message=1   2\r\n3   4", InputFormat::PlainText, chunk);
+            assert_eq!(plain.unwrap(), "1   2\r\n3   4");
+            let raw_text = read_body(b"1   2\n", InputFormat::PlainText, chunk);
+            assert_eq!(raw_text.unwrap(), "1   2\n");
+            let raw_form = read_body(b"1+2%", InputFormat::UrlEncoded, chunk);
+            assert_eq!(raw_form.unwrap(), "1+2%");
+            let encoded = read_body(b"message=1+++2%0D%0a3%2", InputFormat::UrlEncoded, chunk);
+            assert_eq!(encoded.unwrap(), "1   2\r\n3%2");
+            let raw = read_body(b"message=1+2", InputFormat::Raw, chunk);
+            assert_eq!(raw.unwrap(), "message=1+2");
+        }
+    }
+
+    #[test]
+    fn form_fields() {
+        for chunk in 1..10 {
+            let (fields, input) =
+                read_form(b"part=1\r\nx=a&b c\r\nmessage=1   2\n", InputFormat::PlainText, chunk);
+            assert_eq!(fields, "part=1&x=a%26b%20c");
+            assert_eq!(input, "1   2\n");
+
+            let (fields, input) = read_form(b"part=2&message=1+2%0A", InputFormat::UrlEncoded, chunk);
+            assert_eq!(fields, "part=2");
+            assert_eq!(input, "1 2\n");
+
+            // Things that look a bit like fields, but without a message, are just input
+            let (fields, input) = read_form(b"x=1\ny=2\n", InputFormat::PlainText, chunk);
+            assert_eq!(fields, "");
+            assert_eq!(input, "x=1\ny=2\n");
+            let (fields, input) = read_form(b"1   2\nmessage=3", InputFormat::PlainText, chunk);
+            assert_eq!(fields, "");
+            assert_eq!(input, "1   2\nmessage=3");
+        }
+        // Fields have to fit in the buffer. Anything longer is input, unless it's from the page
+        let long = format!("x={}&message=1", "a".repeat(BUF_SIZE));
+        let (fields, input) = read_form(long.as_bytes(), InputFormat::UrlEncoded, 16);
+        assert_eq!(fields, "");
+        assert_eq!(input, long);
+        let long = format!("part=1&expect1={}&message=1", "1".repeat(BUF_SIZE));
+        assert!(matches!(read_body(long.as_bytes(), InputFormat::UrlEncoded, 16), Err(AerError::BadQuery)));
+        // Fields that fit in the buffer but not once they're escaped
+        let escaped = format!("expect1={}\nmessage=1", "&".repeat(BUF_SIZE - 20));
+        assert!(matches!(read_body(escaped.as_bytes(), InputFormat::PlainText, 16), Err(AerError::BadQuery)));
+        // Input that only looks like fields is still input, however long
+        let robots = "p=0,4 v=3,-3\n".repeat(20);
+        let (fields, input) = read_form(robots.as_bytes(), InputFormat::PlainText, 16);
+        assert_eq!((fields.as_str(), input), ("", robots));
+    }
+
+    #[test]
+    fn input_hash() {
+        let hash = |body: &[u8], format, chunk| {
+            embassy_futures::block_on(async {
+                let mut reader = InputReader::new(Chunked { data: body, chunk }, format, body.len())
+                    .await
+                    .unwrap();
+                let size_hint = reader.size_hint();
+                read_input(&mut reader, size_hint).await.unwrap();
+                reader.hash()
+            })
+        };
+        let mut expected = Fnv1a::new();
+        expected.update(b"1   2\n");
+        let expected = expected.finish();
+        for chunk in 1..10 {
+            // Only the input counts, however it was sent
+            assert_eq!(hash(b"1   2\n", InputFormat::Raw, chunk), expected);
+            assert_eq!(hash(b"part=1\r\nmessage=1   2\n", InputFormat::PlainText, chunk), expected);
+            assert_eq!(hash(b"message=1+++2%0A", InputFormat::UrlEncoded, chunk), expected);
+            assert_ne!(hash(b"1   3\n", InputFormat::Raw, chunk), expected);
+        }
+        // Reading what's left after a solver that stopped early
+        let finished = embassy_futures::block_on(async {
+            let body = b"message=1+++2%0A";
+            let mut reader =
+                InputReader::new(Chunked { data: body, chunk: 3 }, InputFormat::UrlEncoded, body.len())
+                    .await
+                    .unwrap();
+            reader.read(&mut [0; 2]).await.unwrap();
+            reader.finish().await.unwrap()
+        });
+        assert_eq!(finished, expected);
+
+        // And keeping a copy on the way
+        let copied = embassy_futures::block_on(async {
+            let body = b"part=1\r\nmessage=1   2\n";
+            let mut reader =
+                InputReader::new(Chunked { data: body, chunk: 5 }, InputFormat::PlainText, body.len())
+                    .await
+                    .unwrap();
+            reader.keep_copy(64);
+            reader.finish().await.unwrap();
+            reader.take_copy()
+        });
+        assert_eq!(copied.as_deref(), Some(&b"1   2\n"[..]));
+    }
+
+    #[test]
+    fn input_limit() {
+        let limited = |body: &[u8], format, limit| {
+            embassy_futures::block_on(async {
+                let mut reader =
+                    InputReader::new(Chunked { data: body, chunk: 4 }, format, body.len()).await.unwrap();
+                reader.limit(limit)?;
+                let size_hint = reader.size_hint();
+                let input = read_input(&mut reader, size_hint).await?;
+                reader.finish().await?;
+                Ok(input)
+            })
+        };
+        // Form fields don't count
+        let body = b"part=1\r\nmessage=1   2\n";
+        assert_eq!(limited(body, InputFormat::PlainText, 6).unwrap(), "1   2\n");
+        assert!(matches!(
+            limited(body, InputFormat::PlainText, 5),
+            Err(AerError::InputTooLarge { size: 6, limit: 5 })
+        ));
+        // Nor does percent-encoding, but it has to be decoded to find that out
+        let body = b"message=1+++2%0A";
+        assert_eq!(limited(body, InputFormat::UrlEncoded, 6).unwrap(), "1   2\n");
+        assert!(matches!(
+            limited(body, InputFormat::UrlEncoded, 5),
+            Err(AerError::InputTooLarge { size: 6, limit: 5 })
+        ));
+        // Too big whichever way it's decoded
+        assert!(matches!(
+            limited(b"message=%0A%0A%0A%0A", InputFormat::UrlEncoded, 3),
+            Err(AerError::InputTooLarge { size: 4, limit: 3 })
+        ));
+    }
+
+    #[test]
+    fn bogus_escapes_pass_through() {
+        for chunk in 1..10 {
+            let decoded = read_body(b"message=%zz%4g%%41%", InputFormat::UrlEncoded, chunk);
+            assert_eq!(decoded.unwrap(), "%zz%4g%A%");
+        }
+    }
+
+    #[test]
+    fn empty_input() {
+        assert!(matches!(
+            read_body(b"message=", InputFormat::PlainText, 4),
+            Err(AerError::MissingMessage)
+        ));
+        assert!(matches!(read_body(b"", InputFormat::Raw, 4), Err(AerError::MissingMessage)));
+    }
+
+    #[test]
+    fn lines_split_across_reads() {
+        for chunk in 1..12 {
+            assert_eq!(read_lines(b"ab\r\ncdefg\n\nhijklmn", chunk), ["ab", "cdefg", "", "hijklmn"]);
+            assert_eq!(read_lines(b"ab\n", chunk), ["ab"]);
+            assert_eq!(read_lines(b"", chunk), Vec::<String>::new());
+        }
+    }
+
+    #[test]
+    fn line_too_long() {
+        embassy_futures::block_on(async {
+            let mut reader = LineReader::<_, 8>::new(Chunked { data: b"123456789\n", chunk: 3 });
+            assert!(matches!(reader.next_line().await, Err(AerError::InputSize { .. })));
+        });
+    }
+}
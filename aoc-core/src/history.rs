@@ -0,0 +1,120 @@
+//! A record of the last few solves, for the history page.
+//!
+//! Only the most recent [`HISTORY_CAPACITY`] are kept, the oldest goes when a new one comes in.
+
+use alloc::string::String;
+use core::cell::RefCell;
+
+use critical_section::Mutex;
+use embassy_time::{Duration, Instant};
+
+use crate::{answer::Answer, error::AerError, mem::try_format, problems::Parts};
+
+/// How many solves are kept.
+pub const HISTORY_CAPACITY: usize = 16;
+
+/// How a solve went.
+#[derive(Clone, Debug)]
+pub enum Outcome {
+    Solved {
+        answer: Answer,
+        /// Whether the answer came from the cache.
+        cached: bool,
+    },
+    /// The error is kept as its code and message, as the error itself can't be cloned.
+    Failed { code: &'static str, message: String },
+}
+
+impl Outcome {
+    /// Without the answer or the message if there's no room left to copy them, the history isn't
+    /// worth running out of memory over.
+    pub fn from_result(result: &crate::Result<Answer>, cached: bool) -> Self {
+        match result {
+            Ok(answer) => {
+                let answer = answer.try_clone().unwrap_or_else(|_| {
+                    warn!("No room to keep the answer in the history");
+                    Answer::new()
+                });
+                Self::Solved { answer, cached }
+            },
+            Err(e) => Self::failed(e),
+        }
+    }
+
+    pub fn failed(e: &AerError) -> Self {
+        Self::Failed { code: e.code(), message: try_format(format_args!("{e}")).unwrap_or_default() }
+    }
+}
+
+/// One solve.
+#[derive(Clone, Debug)]
+pub struct Run {
+    /// When it finished, since boot.
+    pub at: Instant,
+    pub day: u32,
+    pub parts: Parts,
+    /// Decoded, without any form fields. Not known if the solve failed.
+    pub input_len: Option<usize>,
+    /// [`crate::cache::Fnv1a`] of the input. Not known if the solve failed either.
+    pub hash: Option<u64>,
+    pub outcome: Outcome,
+    pub elapsed: Duration,
+    /// Peak heap use during the solve, in bytes.
+    pub heap_peak: usize,
+}
+
+/// Newest first.
+static HISTORY: Mutex<RefCell<heapless::Deque<Run, HISTORY_CAPACITY>>> =
+    Mutex::new(RefCell::new(heapless::Deque::new()));
+
+/// Add a solve to the history, making room if need be.
+pub fn record(run: Run) {
+    // Dropping the old entry can take a while for big answers, keep it out of the critical section
+    let dropped = critical_section::with(|cs| {
+        let mut history = HISTORY.borrow_ref_mut(cs);
+        let dropped = if history.is_full() { history.pop_back() } else { None };
+        // There's always room by now
+        let _ = history.push_front(run);
+        dropped
+    });
+    drop(dropped);
+}
+
+/// Every solve in the history, newest first.
+pub fn runs() -> heapless::Vec<Run, HISTORY_CAPACITY> {
+    critical_section::with(|cs| HISTORY.borrow_ref(cs).iter().cloned().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(day: u32) -> Run {
+        Run {
+            at: Instant::now(),
+            day,
+            parts: Parts::Both,
+            input_len: Some(10),
+            hash: None,
+            outcome: Outcome::failed(&AerError::BadDay { day }),
+            elapsed: Duration::from_millis(1),
+            heap_peak: 0,
+        }
+    }
+
+    // The history is global, so everything that touches it is in the one test
+    #[test]
+    fn newest_first() {
+        for day in 0..HISTORY_CAPACITY + 2 {
+            record(run(u32::try_from(day).unwrap() + 100));
+        }
+        let runs = runs();
+        assert_eq!(runs.len(), HISTORY_CAPACITY);
+        assert_eq!(runs[0].day, 117);
+        assert_eq!(runs[HISTORY_CAPACITY - 1].day, 102);
+        let Outcome::Failed { code, message } = &runs[0].outcome else {
+            panic!("{:?}", runs[0].outcome);
+        };
+        assert_eq!((*code, message.as_str()), ("bad_day", "Day 117 doesn't exist"));
+    }
+}
@@ -0,0 +1,28 @@
+//! Everything that doesn't need the ESP32 itself: the solvers, input parsing, the error type and
+//! the pages. Kept `no_std` so it runs on the board, but it builds for the host as well so that
+//! `cargo test` works without flashing anything.
+#![no_std]
+// Everything here is only used by our own binaries, the API doc lints are just noise.
+#![allow(clippy::missing_errors_doc, clippy::must_use_candidate)]
+
+extern crate alloc;
+
+// Has to come first, the other modules use its macros
+mod fmt;
+
+pub mod answer;
+pub mod app;
+pub mod cache;
+pub mod error;
+pub mod helpers;
+pub mod history;
+pub mod mem;
+pub mod metrics;
+pub mod pages;
+pub mod problems;
+pub mod selftest;
+pub mod stack;
+pub mod storage;
+pub mod timing;
+
+pub use error::Result;
@@ -0,0 +1,197 @@
+//! Fallible allocation, and keeping an eye on the heap.
+//!
+//! The board only has 72 KiB of heap, and running out through the standard collections aborts,
+//! which reboots it and drops every open connection. Anything sized by the input goes through
+//! these helpers instead, so running out of memory is an error page like any other.
+
+use alloc::{string::String, vec::Vec};
+use core::{fmt, ptr};
+
+use portable_atomic::{AtomicPtr, AtomicUsize, Ordering};
+use serde::Serialize;
+
+use crate::{error::AerError, Result};
+
+/// Where to find out about the heap. The firmware asks esp-alloc, the host tools ask their
+/// tracking allocator.
+pub struct HeapProbe {
+    /// Bytes of heap currently free.
+    pub free: fn() -> usize,
+    /// Bytes of heap currently allocated.
+    pub used: fn() -> usize,
+    /// For allocators that keep their own high-water mark. Without one, the peak is sampled
+    /// whenever the helpers in this module allocate. That catches everything sized by the input,
+    /// but can miss small short-lived allocations.
+    pub peak: Option<PeakTracker>,
+}
+
+/// An allocator's own high-water mark.
+pub struct PeakTracker {
+    /// Highest usage since the last reset, in bytes.
+    pub peak: fn() -> usize,
+    /// Start a new high-water mark from current usage.
+    pub reset: fn(),
+}
+
+static PROBE: AtomicPtr<HeapProbe> = AtomicPtr::new(ptr::null_mut());
+/// Highest usage seen by the helpers since the last [`HeapWatch::start`].
+static SAMPLED_PEAK: AtomicUsize = AtomicUsize::new(0);
+/// Highest peak of any solve since boot.
+static PEAK_SINCE_BOOT: AtomicUsize = AtomicUsize::new(0);
+
+/// Install the heap probe. Call it once at start up, before serving anything.
+pub fn set_heap_probe(probe: &'static HeapProbe) {
+    PROBE.store(ptr::from_ref(probe).cast_mut(), Ordering::Release);
+}
+
+fn probe() -> Option<&'static HeapProbe> {
+    let probe = PROBE.load(Ordering::Acquire);
+    // SAFETY: the only non-null pointers ever stored come from `&'static HeapProbe`s
+    unsafe { probe.as_ref() }
+}
+
+/// Bytes of heap free right now, or 0 if no probe has been installed.
+pub fn free_heap() -> usize {
+    probe().map_or(0, |probe| (probe.free)())
+}
+
+/// Bytes of heap allocated right now, or 0 if no probe has been installed.
+pub fn used_heap() -> usize {
+    probe().map_or(0, |probe| (probe.used)())
+}
+
+/// The most heap allocated during any solve since boot, in bytes.
+pub fn peak_heap() -> usize {
+    PEAK_SINCE_BOOT.load(Ordering::Relaxed)
+}
+
+/// Heap figures for one solve, in bytes of heap allocated.
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct HeapUsage {
+    /// Before the solve started.
+    pub before: usize,
+    /// The most at any point during the solve.
+    pub peak: usize,
+    /// Still held once it finished, answers included.
+    pub after: usize,
+}
+
+/// Watches the heap over a solve. There's only the one high-water mark, so with several solves
+/// running at once each one's peak includes the others.
+pub struct HeapWatch {
+    before: usize,
+}
+
+impl HeapWatch {
+    pub fn start() -> Self {
+        let before = used_heap();
+        SAMPLED_PEAK.store(before, Ordering::Relaxed);
+        if let Some(tracker) = probe().and_then(|probe| probe.peak.as_ref()) {
+            (tracker.reset)();
+        }
+        Self { before }
+    }
+
+    pub fn finish(self) -> HeapUsage {
+        let after = used_heap();
+        let tracked = probe()
+            .and_then(|probe| probe.peak.as_ref())
+            .map_or(0, |tracker| (tracker.peak)());
+        let peak = SAMPLED_PEAK.load(Ordering::Relaxed).max(tracked).max(after);
+        PEAK_SINCE_BOOT.fetch_max(peak, Ordering::Relaxed);
+        HeapUsage { before: self.before, peak, after }
+    }
+}
+
+fn out_of_memory(needed: usize) -> AerError {
+    let free = free_heap();
+    error!("Out of memory, needed {} bytes with {} free", needed, free);
+    AerError::OutOfMemory { needed, free }
+}
+
+/// `Vec::reserve`, but running out of memory is an error. The error reports the size of the
+/// whole buffer that was asked for, not just the extra.
+pub fn try_reserve<T>(v: &mut Vec<T>, additional: usize) -> Result<()> {
+    let capacity = v.capacity();
+    v.try_reserve(additional).map_err(|_| {
+        out_of_memory(v.len().saturating_add(additional).saturating_mul(size_of::<T>()))
+    })?;
+    if v.capacity() != capacity {
+        SAMPLED_PEAK.fetch_max(used_heap(), Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+/// `Vec::with_capacity`, but running out of memory is an error.
+pub fn try_vec<T>(capacity: usize) -> Result<Vec<T>> {
+    let mut v = Vec::new();
+    try_reserve(&mut v, capacity)?;
+    Ok(v)
+}
+
+/// `String::from`, but running out of memory is an error.
+pub fn try_string(s: &str) -> Result<String> {
+    let mut v = try_vec(s.len())?;
+    v.extend_from_slice(s.as_bytes());
+    Ok(String::from_utf8(v)?)
+}
+
+/// `format!`, but running out of memory is an error.
+pub fn try_format(args: fmt::Arguments<'_>) -> Result<String> {
+    struct Writer {
+        buf: Vec<u8>,
+        out_of_memory: Option<AerError>,
+    }
+
+    impl fmt::Write for Writer {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            if let Err(e) = try_reserve(&mut self.buf, s.len()) {
+                self.out_of_memory = Some(e);
+                return Err(fmt::Error);
+            }
+            self.buf.extend_from_slice(s.as_bytes());
+            Ok(())
+        }
+    }
+
+    let mut w = Writer { buf: Vec::new(), out_of_memory: None };
+    if let Err(e) = fmt::write(&mut w, args) {
+        return Err(w.out_of_memory.unwrap_or(AerError::FmtError(e)));
+    }
+    Ok(String::from_utf8(w.buf)?)
+}
+
+/// `Vec::push`, but running out of memory is an error.
+pub trait TryPush<T> {
+    fn try_push(&mut self, value: T) -> Result<()>;
+}
+
+impl<T> TryPush<T> for Vec<T> {
+    fn try_push(&mut self, value: T) -> Result<()> {
+        if self.len() == self.capacity() {
+            try_reserve(self, 1)?;
+        }
+        self.push(value);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn impossible_allocations_are_errors() {
+        let err = try_vec::<u64>(usize::MAX / 4).unwrap_err();
+        assert!(matches!(err, AerError::OutOfMemory { needed: usize::MAX, .. }), "{err:?}");
+
+        let mut v = try_vec::<u8>(4).unwrap();
+        for b in 0..100 {
+            v.try_push(b).unwrap();
+        }
+        assert_eq!(v.len(), 100);
+
+        assert_eq!(try_string("grid\n").unwrap(), "grid\n");
+        assert_eq!(try_format(format_args!("day {}", 3)).unwrap(), "day 3");
+    }
+}
@@ -0,0 +1,354 @@
+//! Counters and gauges for `/metrics`, in the Prometheus text exposition format.
+//!
+//! Metrics are statics wherever they're counted, and go into a registry to be listed. Counters
+//! and families put themselves in it the first time they're touched, gauges and anything that
+//! should show up before it's happened get [`register`]ed at start up. The process figures here,
+//! uptime and heap, are always listed.
+
+use core::{
+    cell::RefCell,
+    fmt::{self, Write},
+};
+
+use critical_section::Mutex;
+use embassy_time::{Duration, Instant};
+use portable_atomic::{AtomicBool, AtomicI32, AtomicU32, Ordering};
+
+use crate::mem;
+
+/// How many metrics can be registered, not counting the built in ones.
+pub const REGISTRY_CAPACITY: usize = 32;
+
+/// Something that can be listed on `/metrics`.
+pub trait Metric: Sync {
+    fn name(&self) -> &'static str;
+    /// Write it out in the text exposition format, `# HELP` and `# TYPE` lines and all.
+    fn expose(&self, w: &mut dyn Write) -> fmt::Result;
+}
+
+fn write_header(w: &mut dyn Write, name: &str, help: &str, kind: &str) -> fmt::Result {
+    writeln!(w, "# HELP {name} {help}")?;
+    writeln!(w, "# TYPE {name} {kind}")
+}
+
+/// Seconds, in the exposition format's floating point.
+struct Seconds(Duration);
+
+impl fmt::Display for Seconds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let micros = self.0.as_micros();
+        write!(f, "{}.{:06}", micros / 1_000_000, micros % 1_000_000)
+    }
+}
+
+static REGISTRY: Mutex<RefCell<heapless::Vec<&'static dyn Metric, REGISTRY_CAPACITY>>> =
+    Mutex::new(RefCell::new(heapless::Vec::new()));
+
+/// Add `metric` to the registry, if it isn't already there.
+pub fn register(metric: &'static dyn Metric) {
+    let full = critical_section::with(|cs| {
+        let mut registry = REGISTRY.borrow_ref_mut(cs);
+        if registry.iter().any(|m| m.name() == metric.name()) {
+            return false;
+        }
+        registry.push(metric).is_err()
+    });
+    if full {
+        warn!("No room to register metric {}", metric.name());
+    }
+}
+
+/// Write out every metric, the built in ones first.
+pub fn expose(w: &mut dyn Write) -> fmt::Result {
+    for metric in BUILTIN {
+        metric.expose(w)?;
+    }
+    // Exposing can take a while, so it's done from a copy
+    let registered = critical_section::with(|cs| REGISTRY.borrow_ref(cs).clone());
+    for metric in registered {
+        metric.expose(w)?;
+    }
+    Ok(())
+}
+
+/// A count that only goes up.
+pub struct Counter {
+    name: &'static str,
+    help: &'static str,
+    value: AtomicU32,
+    registered: AtomicBool,
+}
+
+impl Counter {
+    pub const fn new(name: &'static str, help: &'static str) -> Self {
+        Self { name, help, value: AtomicU32::new(0), registered: AtomicBool::new(false) }
+    }
+
+    /// Add one. Returns the count from before.
+    pub fn inc(&'static self) -> u32 {
+        self.add(1)
+    }
+
+    /// Add `n`. Returns the count from before.
+    pub fn add(&'static self, n: u32) -> u32 {
+        if !self.registered.swap(true, Ordering::Relaxed) {
+            register(self);
+        }
+        self.value.fetch_add(n, Ordering::Relaxed)
+    }
+
+    pub fn get(&self) -> u32 {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+impl Metric for Counter {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn expose(&self, w: &mut dyn Write) -> fmt::Result {
+        write_header(w, self.name, self.help, "counter")?;
+        writeln!(w, "{} {}", self.name, self.get())
+    }
+}
+
+/// A reading that's set from time to time, and left out until it first is.
+pub struct Gauge {
+    name: &'static str,
+    help: &'static str,
+    value: AtomicI32,
+    set: AtomicBool,
+}
+
+impl Gauge {
+    pub const fn new(name: &'static str, help: &'static str) -> Self {
+        Self { name, help, value: AtomicI32::new(0), set: AtomicBool::new(false) }
+    }
+
+    pub fn set(&self, value: i32) {
+        self.value.store(value, Ordering::Relaxed);
+        self.set.store(true, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> Option<i32> {
+        self.set.load(Ordering::Relaxed).then(|| self.value.load(Ordering::Relaxed))
+    }
+}
+
+impl Metric for Gauge {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn expose(&self, w: &mut dyn Write) -> fmt::Result {
+        write_header(w, self.name, self.help, "gauge")?;
+        match self.get() {
+            Some(value) => writeln!(w, "{} {value}", self.name),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A reading worked out whenever it's asked for.
+pub struct GaugeFn {
+    name: &'static str,
+    help: &'static str,
+    read: fn() -> u64,
+}
+
+impl GaugeFn {
+    pub const fn new(name: &'static str, help: &'static str, read: fn() -> u64) -> Self {
+        Self { name, help, read }
+    }
+}
+
+impl Metric for GaugeFn {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn expose(&self, w: &mut dyn Write) -> fmt::Result {
+        write_header(w, self.name, self.help, "gauge")?;
+        writeln!(w, "{} {}", self.name, (self.read)())
+    }
+}
+
+/// Labels for one series in a family, as they go between the braces, e.g. `day="1"`.
+pub type Labels = heapless::String<48>;
+
+struct Series {
+    labels: Labels,
+    count: u32,
+    sum: Duration,
+}
+
+/// Whether a family counts things, or times them too.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Counter,
+    /// Exposed as a summary, with a `_count` and a `_sum` in seconds per series.
+    Timer,
+}
+
+/// The same metric, split up by labels. Holds up to `N` series; anything past that is dropped.
+pub struct Family<const N: usize> {
+    name: &'static str,
+    help: &'static str,
+    kind: Kind,
+    series: Mutex<RefCell<heapless::Vec<Series, N>>>,
+    registered: AtomicBool,
+}
+
+impl<const N: usize> Family<N> {
+    /// A family of counters.
+    pub const fn counter(name: &'static str, help: &'static str) -> Self {
+        Self::new(name, help, Kind::Counter)
+    }
+
+    /// A family of timings, e.g. `aoc_solve_duration_seconds`.
+    pub const fn timer(name: &'static str, help: &'static str) -> Self {
+        Self::new(name, help, Kind::Timer)
+    }
+
+    const fn new(name: &'static str, help: &'static str, kind: Kind) -> Self {
+        Self {
+            name,
+            help,
+            kind,
+            series: Mutex::new(RefCell::new(heapless::Vec::new())),
+            registered: AtomicBool::new(false),
+        }
+    }
+
+    /// Count one more for `labels`.
+    pub fn inc(&'static self, labels: fmt::Arguments<'_>) {
+        self.observe(labels, Duration::MIN);
+    }
+
+    /// Count one more for `labels`, which took `elapsed`.
+    pub fn observe(&'static self, labels: fmt::Arguments<'_>, elapsed: Duration) {
+        if !self.registered.swap(true, Ordering::Relaxed) {
+            register(self);
+        }
+        let mut key = Labels::new();
+        if key.write_fmt(labels).is_err() {
+            warn!("Labels too long for metric {}", self.name);
+            return;
+        }
+        let full = critical_section::with(|cs| {
+            let mut series = self.series.borrow_ref_mut(cs);
+            if let Some(series) = series.iter_mut().find(|series| series.labels == key) {
+                series.count += 1;
+                series.sum += elapsed;
+                return false;
+            }
+            series.push(Series { labels: key, count: 1, sum: elapsed }).is_err()
+        });
+        if full {
+            warn!("No room for another series of metric {}", self.name);
+        }
+    }
+
+    /// How many have been counted for `labels`.
+    pub fn get(&self, labels: &str) -> u32 {
+        critical_section::with(|cs| {
+            let series = self.series.borrow_ref(cs);
+            series.iter().find(|series| series.labels == labels).map_or(0, |series| series.count)
+        })
+    }
+}
+
+impl<const N: usize> Metric for Family<N> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn expose(&self, w: &mut dyn Write) -> fmt::Result {
+        let snapshot: heapless::Vec<(Labels, u32, Duration), N> = critical_section::with(|cs| {
+            let series = self.series.borrow_ref(cs);
+            series.iter().map(|series| (series.labels.clone(), series.count, series.sum)).collect()
+        });
+        let name = self.name;
+        match self.kind {
+            Kind::Counter => {
+                write_header(w, name, self.help, "counter")?;
+                for (labels, count, _) in &snapshot {
+                    writeln!(w, "{name}{{{labels}}} {count}")?;
+                }
+            },
+            Kind::Timer => {
+                write_header(w, name, self.help, "summary")?;
+                for (labels, count, sum) in &snapshot {
+                    writeln!(w, "{name}_count{{{labels}}} {count}")?;
+                    writeln!(w, "{name}_sum{{{labels}}} {}", Seconds(*sum))?;
+                }
+            },
+        }
+        Ok(())
+    }
+}
+
+static UPTIME: GaugeFn =
+    GaugeFn::new("aoc_uptime_seconds", "Seconds since boot.", || Instant::now().as_secs());
+static HEAP_FREE: GaugeFn =
+    GaugeFn::new("aoc_heap_free_bytes", "Bytes of heap free.", || mem::free_heap() as u64);
+static HEAP_USED: GaugeFn =
+    GaugeFn::new("aoc_heap_used_bytes", "Bytes of heap allocated.", || mem::used_heap() as u64);
+static HEAP_PEAK: GaugeFn = GaugeFn::new(
+    "aoc_heap_peak_bytes",
+    "Most bytes of heap allocated during any solve since boot.",
+    || mem::peak_heap() as u64,
+);
+
+static BUILTIN: [&dyn Metric; 4] = [&UPTIME, &HEAP_FREE, &HEAP_USED, &HEAP_PEAK];
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use super::*;
+
+    static COUNTER: Counter = Counter::new("test_things_total", "Things.");
+    static GAUGE: Gauge = Gauge::new("test_level", "Level.");
+    static TIMES: Family<2> = Family::timer("test_duration_seconds", "Durations.");
+
+    // The registry is global, so everything that touches it is in the one test
+    #[test]
+    fn exposition() {
+        register(&GAUGE);
+        assert_eq!(COUNTER.inc(), 0);
+        COUNTER.add(2);
+        TIMES.observe(format_args!("day=\"{}\"", 1), Duration::from_micros(1_500_000));
+        TIMES.observe(format_args!("day=\"{}\"", 1), Duration::from_micros(250));
+        TIMES.observe(format_args!("day=\"{}\"", 2), Duration::from_millis(3));
+        // No room for a third series
+        TIMES.observe(format_args!("day=\"{}\"", 3), Duration::from_millis(3));
+        assert_eq!(TIMES.get("day=\"1\""), 2);
+
+        let mut text = String::new();
+        expose(&mut text).unwrap();
+        assert!(text.starts_with(
+            "# HELP aoc_uptime_seconds Seconds since boot.\n# TYPE aoc_uptime_seconds gauge\n"
+        ));
+        assert!(text.contains("# TYPE test_things_total counter\ntest_things_total 3\n"), "{text}");
+        // Not set yet
+        assert!(text.contains("# TYPE test_level gauge\n# HELP"), "{text}");
+        assert!(text.contains("test_duration_seconds_count{day=\"1\"} 2\n"), "{text}");
+        assert!(text.contains("test_duration_seconds_sum{day=\"1\"} 1.500250\n"));
+        assert!(text.contains("test_duration_seconds_sum{day=\"2\"} 0.003000\n"));
+        assert!(!text.contains("day=\"3\""));
+
+        GAUGE.set(-61);
+        let mut text = String::new();
+        expose(&mut text).unwrap();
+        assert!(text.contains("test_level -61\n"));
+        // Registering again doesn't list it twice
+        register(&GAUGE);
+        register(&COUNTER);
+        let mut again = String::new();
+        expose(&mut again).unwrap();
+        assert_eq!(again.matches("# TYPE test_level").count(), 1);
+        assert_eq!(again.matches("# TYPE test_things_total").count(), 1);
+    }
+}
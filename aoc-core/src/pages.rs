@@ -0,0 +1,349 @@
+use core::{fmt::Write, marker::PhantomData};
+
+mod api;
+pub use api::Api;
+
+mod bench;
+pub use bench::{do_bench, MAX_BENCH_RUNS};
+
+mod cache;
+pub use cache::{Cache, ClearCache};
+
+mod history;
+pub use history::History;
+
+mod index;
+pub use index::Index;
+
+mod input;
+pub use input::Input;
+
+mod metrics;
+pub use metrics::Metrics;
+
+mod saved;
+pub use saved::RunSaved;
+
+mod selftest;
+pub use selftest::SelfTest;
+
+mod solver;
+use picoserve::{
+    io::Read,
+    response::{
+        chunked::{ChunkWriter, ChunkedResponse, Chunks, ChunksWritten},
+        Connection, IntoResponse, ResponseWriter, StatusCode,
+    },
+    ResponseSent,
+};
+pub use solver::{
+    do_problem, lookup_problem, run_problem, Lookup, Run, SolveOptions, Solver, CHECK_FAILED,
+};
+
+use crate::{
+    answer::{Answer, Checks, Value},
+    error::AerError,
+    mem::try_reserve,
+    problems::Parts,
+    timing::{Stats, Timings},
+    Result,
+};
+
+pub const HTML_HEADER: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="UTF-8">
+<meta name="viewport" content="width=device-width, initial-scale=1.0">
+<title>AOC on ESP32</title>
+<link rel="stylesheet" href="/static/index.css">
+<link rel="icon" href="/static/icon.png">
+</head>
+<body>"#;
+
+pub const HTML_FOOTER: &str = r#"<hr><a href="/">Return Home</a><br></body></html>"#;
+
+/// Displays a string with the HTML special characters escaped.
+/// Answers and notes can contain anything, so they go through this before hitting a page.
+pub struct Escaped<'a>(pub &'a str);
+
+impl core::fmt::Display for Escaped<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut rest = self.0;
+        while let Some(idx) = rest.find(['<', '>', '&', '"']) {
+            f.write_str(&rest[..idx])?;
+            f.write_str(match rest.as_bytes()[idx] {
+                b'<' => "&lt;",
+                b'>' => "&gt;",
+                b'&' => "&amp;",
+                _ => "&quot;",
+            })?;
+            rest = &rest[idx + 1..];
+        }
+        f.write_str(rest)
+    }
+}
+
+/// Displays one part's answer as HTML. Grids keep their layout, and text is escaped. Everywhere
+/// an answer goes on a page, it goes through this.
+pub struct HtmlValue<'a>(pub &'a Value);
+
+impl core::fmt::Display for HtmlValue<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.0 {
+            Value::Grid(grid) => write!(f, "<pre>{}</pre>", Escaped(grid)),
+            Value::Str(v) => Escaped(v).fmt(f),
+            v => v.fmt(f),
+        }
+    }
+}
+
+/// Render a solver's answer as HTML. This is the only place that decides what a result looks like.
+/// Parts that weren't asked for are left out, and parts that were checked say how it went.
+pub fn write_answer<W: Write>(
+    w: &mut W,
+    answer: &Answer,
+    parts: Parts,
+    checks: &Checks,
+) -> core::fmt::Result {
+    let all_parts = [
+        (1, &answer.part1, parts.part1(), &checks.part1),
+        (2, &answer.part2, parts.part2(), &checks.part2),
+    ];
+    for (part, value, wanted, check) in all_parts {
+        if !wanted {
+            continue;
+        }
+        match value {
+            Some(v) => write!(w, "Part {part}: {}", HtmlValue(v))?,
+            None => write!(w, "Part {part}: no answer")?,
+        }
+        if let Some(check) = check {
+            let (class, verdict) = if check.pass { ("pass", "&#10004;") } else { ("fail", "&#10008;") };
+            write!(w, r#" <span class="{class}">{verdict} expected {}</span>"#, Escaped(&check.expected))?;
+        }
+        // The grid's `<pre>` already breaks the line
+        if matches!(value, Some(Value::Grid(_))) && check.is_none() {
+            writeln!(w)?;
+        } else {
+            writeln!(w, "<br>")?;
+        }
+    }
+    for note in &answer.notes {
+        writeln!(w, "{}<br>", Escaped(note))?;
+    }
+    Ok(())
+}
+
+/// Render the time spent in each phase of a solve as a table, in microseconds.
+pub fn write_timings<W: Write>(w: &mut W, timings: &Timings) -> core::fmt::Result {
+    writeln!(w, "<table><tr><th>Phase</th><th>Time (µs)</th></tr>")?;
+    let mut total = 0;
+    for (phase, time) in timings.phases() {
+        writeln!(w, "<tr><td>{}</td><td>{}</td></tr>", phase.name(), time.as_micros())?;
+        total += time.as_micros();
+    }
+    writeln!(w, "<tr><th>Total</th><th>{total}</th></tr></table>")
+}
+
+/// Render a benchmark's figures as a table, in microseconds.
+pub fn write_stats<W: Write>(w: &mut W, stats: &Stats) -> core::fmt::Result {
+    writeln!(w, "<table><tr><th>Runs</th><th>Min (µs)</th><th>Median (µs)</th>")?;
+    writeln!(w, "<th>Mean (µs)</th><th>Max (µs)</th></tr>")?;
+    writeln!(
+        w,
+        "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr></table>",
+        stats.runs,
+        stats.min.as_micros(),
+        stats.median.as_micros(),
+        stats.mean.as_micros(),
+        stats.max.as_micros()
+    )
+}
+
+/// Render the page for a request that failed outright. The status comes from the error.
+pub fn error_page(e: &AerError) -> Result<HtmlPage> {
+    let mut page = HtmlPage::new().with_status(e.status());
+    page.insert_header()?;
+    writeln!(page, "<h1>Error {}</h1>", e.status())?;
+    writeln!(page, "<h2>{e}</h2>")?;
+    page.insert_footer()?;
+    Ok(page)
+}
+
+/// Respond with the error page for `e`. Every handler reports its errors through this.
+pub async fn write_error<R: Read, W: ResponseWriter<Error = R::Error>>(
+    e: &AerError,
+    connection: Connection<'_, R>,
+    w: W,
+) -> core::result::Result<ResponseSent, W::Error> {
+    match error_page(e) {
+        Ok(page) => page.write_to(connection, w).await,
+        // Can't even render a page, just get the status out
+        Err(_) => (e.status(), format_args!("Error {}: {e}", e.status()))
+            .write_to(connection, w)
+            .await,
+    }
+}
+
+/// What a [`Page`] is sent as.
+pub trait ContentType {
+    const CONTENT_TYPE: &'static str;
+}
+
+/// Nearly every page.
+pub struct Html;
+
+impl ContentType for Html {
+    const CONTENT_TYPE: &'static str = "text/html";
+}
+
+/// A helper type for rendering pages. It can be used to insert default headers & footers, 
+/// and implements various `fmt::Write` traits to allow use of `writeln!` macros.
+/// 
+/// I won't offend smarter people by calling it "templating", and there is no `no_std` template engine.
+///
+/// `K` is what it's sent as, HTML unless it's for the odd endpoint that's read by programs.
+pub struct Page<K: ContentType> {
+    content: alloc::vec::Vec<u8>,
+    status: StatusCode,
+    /// Why the last write failed, if it was for want of heap. See [`Page::error`].
+    out_of_memory: Option<AerError>,
+    kind: PhantomData<K>,
+}
+
+pub type HtmlPage = Page<Html>;
+
+impl<K: ContentType> Default for Page<K> {
+    fn default() -> Self {
+        Self {
+            content: alloc::vec::Vec::new(),
+            status: StatusCode::OK,
+            out_of_memory: None,
+            kind: PhantomData,
+        }
+    }
+}
+
+impl<K: ContentType> Page<K> {
+    /// Create a new `Page`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the status the page is sent with, 200 OK unless told otherwise.
+    #[must_use]
+    pub fn with_status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn set_status(&mut self, status: StatusCode) {
+        self.status = status;
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Provide a size hint for the internal buffer. 
+    /// Can speed up page rendering if you roughly know in advance how big your page will be.
+    /// Fails if the heap can't spare that much, which is nicer than failing halfway through.
+    pub fn with_size_hint(mut self, hint: usize) -> Result<Self> {
+        let additional = hint.saturating_sub(self.content.len());
+        try_reserve(&mut self.content, additional)?;
+        Ok(self)
+    }
+
+    /// What `write!` and `writeln!` on a page call. A write that runs out of heap fails with the
+    /// [`AerError::OutOfMemory`] it ran into, rather than as a formatting error.
+    pub fn write_fmt(&mut self, args: core::fmt::Arguments<'_>) -> Result<()> {
+        Write::write_fmt(self, args).map_err(|e| self.error(e))
+    }
+
+    /// What a formatting error from writing to the page really was. Pages only fail for want of
+    /// heap, so unless it came from something being formatted, that's the error.
+    /// For anything that writes to the page through `fmt::Write`, e.g. [`write_answer`].
+    pub fn error(&mut self, e: core::fmt::Error) -> AerError {
+        self.out_of_memory.take().unwrap_or(AerError::FmtError(e))
+    }
+
+    pub fn len(&self) -> usize {
+        self.content.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.content.is_empty()
+    }
+
+    /// The page rendered so far.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.content
+    }
+
+    /// Convert this page into a chunk writer, suitable for sending as a reply.
+    /// This is mandatory, more specifically it is required for any page that is larger than your TCP MTU.
+    pub fn into_chunks(self) -> ChunkedResponse<Self> {
+        ChunkedResponse::new(self)
+    }
+}
+
+impl HtmlPage {
+    /// Append the header to the end of the buffer. 
+    /// It's expected that you'll call this function first and once.
+    /// You can call this multiple times but you'll get multiple invalid headers. Do you want that?
+    pub fn insert_header(&mut self) -> Result<()> {
+        self.write_str(HTML_HEADER).map_err(|e| self.error(e))
+    }
+
+    /// Inserts the footer. Do this last before you return.
+    pub fn insert_footer(&mut self) -> Result<()> {
+        self.write_str(HTML_FOOTER).map_err(|e| self.error(e))
+    }
+}
+
+impl<K: ContentType> Chunks for Page<K> {
+    fn content_type(&self) -> &'static str {
+        K::CONTENT_TYPE
+    }
+    
+    async fn write_chunks<W: picoserve::io::Write>(
+        self,
+        mut chunk_writer: ChunkWriter<W>,
+    ) -> core::result::Result<ChunksWritten, W::Error> {
+        // just a guess at an appropriate chunk size? Completely depends on the TCP MTU but we 
+        // can't detect that.
+        for chunk in self.content.chunks(1200) {
+            chunk_writer.write_chunk(chunk).await?;
+        }
+        chunk_writer.finalize().await
+    }
+}
+
+/// Sends the page in chunks, with its status.
+impl<K: ContentType> IntoResponse for Page<K> {
+    async fn write_to<R: Read, W: ResponseWriter<Error = R::Error>>(
+        self,
+        connection: Connection<'_, R>,
+        w: W,
+    ) -> core::result::Result<ResponseSent, W::Error> {
+        let status = self.status;
+        self.into_chunks()
+            .into_response()
+            .with_status_code(status)
+            .write_to(connection, w)
+            .await
+    }
+}
+
+impl<K: ContentType> core::fmt::Write for Page<K> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        // Running out of heap halfway through a page has to come out as a formatting error, what
+        // it really was is kept for `Page::error`
+        if let Err(e) = try_reserve(&mut self.content, s.len()) {
+            self.out_of_memory = Some(e);
+            return Err(core::fmt::Error);
+        }
+        self.content.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use alloc::string::{String, ToString};
+use picoserve::response::{IntoResponse, Json, StatusCode};
+use serde::Serialize;
+
+use crate::{
+    answer::{Check, Value},
+    error::AerError,
+    helpers::InputFormat,
+    mem::HeapUsage,
+    pages::solver::{run_problem, Run, SolveOptions, CHECK_FAILED},
+    stack::StackUsage,
+};
+
+/// Machine readable equivalent of [`AerError`]
+#[derive(Serialize)]
+struct ApiError {
+    code: &'static str,
+    message: String,
+}
+
+impl From<&AerError> for ApiError {
+    fn from(e: &AerError) -> Self {
+        Self {
+            code: e.code(),
+            message: e.to_string(),
+        }
+    }
+}
+
+/// Body of every `/api/day/{n}` response.
+#[derive(Serialize)]
+struct ApiResponse {
+    day: u32,
+    part1: Option<Value>,
+    part2: Option<Value>,
+    /// Only there if an expected answer was given for that part.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    check1: Option<Check>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    check2: Option<Check>,
+    elapsed_ms: u64,
+    heap: HeapUsage,
+    stack: Option<StackUsage>,
+    /// The answer and figures are from an earlier solve of the same input.
+    cached: bool,
+    error: Option<ApiError>,
+}
+
+/// JSON endpoint for scripts. Takes the puzzle input as the request body, preferably raw.
+pub struct Api;
+
+impl picoserve::routing::RequestHandlerService<(), (u32,)> for Api {
+    async fn call_request_handler_service<
+        R: embedded_io_async::Read,
+        W: picoserve::response::ResponseWriter<Error = R::Error>,
+    >(
+        &self,
+        _state: &(),
+        (day,): (u32,),
+        mut r: picoserve::request::Request<'_, R>,
+        w: W,
+    ) -> core::result::Result<picoserve::ResponseSent, W::Error> {
+        let content_length = r.body_connection.content_length();
+        let format = InputFormat::from_content_type(
+            r.parts.headers().get("Content-Type").map(|v| v.as_raw()),
+        );
+        info!("API request for day {}, input length {}, format {}", day, content_length, format);
+
+        let run = match SolveOptions::parse(r.parts.query().unwrap_or_default()) {
+            Ok(options) => {
+                let body = r.body_connection.body().reader();
+                run_problem(body, format, day, content_length, options).await
+            },
+            Err(e) => Run::failed(e),
+        };
+        let Run { result, options, elapsed, heap, stack, cached, .. } = run;
+        let elapsed_ms = elapsed.as_millis();
+
+        let mut status = result.as_ref().map_or_else(AerError::status, |_| StatusCode::OK);
+        let response = match result {
+            Ok(answer) => {
+                let checks = options.checks(&answer);
+                if !checks.passed() {
+                    status = CHECK_FAILED;
+                }
+                ApiResponse {
+                    day,
+                    part1: answer.part1,
+                    part2: answer.part2,
+                    check1: checks.part1,
+                    check2: checks.part2,
+                    elapsed_ms,
+                    heap,
+                    stack,
+                    cached,
+                    error: None,
+                }
+            },
+            Err(e) => {
+                error!("Error when doing problem via API: {:?}", e);
+                ApiResponse {
+                    day,
+                    part1: None,
+                    part2: None,
+                    check1: None,
+                    check2: None,
+                    elapsed_ms,
+                    heap,
+                    stack,
+                    cached,
+                    error: Some((&e).into()),
+                }
+            },
+        };
+        Json(response)
+            .into_response()
+            .with_status_code(status)
+            .write_to(r.body_connection.finalize().await?, w)
+            .await
+    }
+}
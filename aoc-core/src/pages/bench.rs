@@ -0,0 +1,97 @@
+use embassy_time::{Duration, Instant};
+
+use crate::{
+    error::AerError,
+    helpers::{read_input, InputFormat, InputReader},
+    mem::{try_vec, HeapWatch, TryPush},
+    pages::{solver::CHECK_FAILED, write_answer, write_stats, HtmlPage, SolveOptions},
+    problems::{self, Context},
+    timing::Stats,
+    Result,
+};
+
+/// Most runs one benchmark can ask for. The board can't serve anything else in the meantime.
+pub const MAX_BENCH_RUNS: u32 = 100;
+
+/// Solve `day` `runs` times over for the input in `r`, and render the figures.
+///
+/// The input is read into memory first, so none of the runs wait on the network. Only the first
+/// run's answers make it onto the page, the rest are thrown away. The first failed run stops the
+/// benchmark, and the page is sent with its error's status.
+pub async fn do_bench<R: picoserve::io::Read>(
+    r: R,
+    format: InputFormat,
+    day: u32,
+    input_len: usize,
+    runs: u32,
+    mut options: SolveOptions,
+) -> Result<HtmlPage> {
+    info!("Benchmarking day {} over {} runs", day, runs);
+    let entry = problems::get(day)?;
+    if !(1..=MAX_BENCH_RUNS).contains(&runs) {
+        return Err(AerError::BenchRuns { runs, max: MAX_BENCH_RUNS });
+    }
+
+    let start = Instant::now();
+    let mut reader = InputReader::new(r, format, input_len).await?;
+    reader.limit(entry.input_limit())?;
+    let size_hint = reader.size_hint();
+    let input = read_input(&mut reader, size_hint).await?;
+    reader.finish().await?;
+    let read_time = start.elapsed();
+    options.merge(SolveOptions::parse(reader.form_fields())?);
+    let parts = options.parts();
+
+    let mut page = HtmlPage::new().with_size_hint(2048)?;
+    page.insert_header()?;
+    writeln!(page, "<h1>Advent of Code day {day}: {} (benchmark)</h1><hr>", entry.title)?;
+    writeln!(page, "<code>")?;
+
+    let mut times: alloc::vec::Vec<Duration> = try_vec(runs as usize)?;
+    let mut heap_peak = 0;
+    for run in 0..runs {
+        let heap_watch = HeapWatch::start();
+        let start = Instant::now();
+        let mut cx = Context::new().with_parts(parts);
+        let result = problems::solve(day, &mut input.as_bytes(), input.len(), &mut cx).await;
+        let elapsed = start.elapsed();
+        heap_peak = heap_peak.max(heap_watch.finish().peak);
+        match result {
+            Ok(answer) if run == 0 => {
+                let checks = options.checks(&answer);
+                if !checks.passed() {
+                    page.set_status(CHECK_FAILED);
+                }
+                write_answer(&mut page, &answer, parts, &checks).map_err(|e| page.error(e))?;
+            },
+            Ok(_) => (),
+            Err(e) => {
+                error!("Benchmark run {} failed: {:?}", run, e);
+                page.set_status(e.status());
+                writeln!(page, "<br>Run {} encountered error: {e}<br>", run + 1)?;
+                break;
+            },
+        }
+        times.try_push(elapsed)?;
+        // Let the network stack have a go between runs
+        embassy_futures::yield_now().await;
+    }
+
+    writeln!(page, "Input read in {}µs, which isn't counted below<br>", read_time.as_micros())?;
+    writeln!(page, "Heap peaked at {heap_peak} bytes<br>")?;
+    writeln!(page, "</code>")?;
+    if let Some(stats) = Stats::from_samples(&mut times) {
+        info!(
+            "Day {} over {} runs: min {}us, median {}us, mean {}us, max {}us",
+            day,
+            stats.runs,
+            stats.min.as_micros(),
+            stats.median.as_micros(),
+            stats.mean.as_micros(),
+            stats.max.as_micros()
+        );
+        write_stats(&mut page, &stats).map_err(|e| page.error(e))?;
+    }
+    page.insert_footer()?;
+    Ok(page)
+}
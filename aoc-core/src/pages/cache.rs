@@ -0,0 +1,97 @@
+use picoserve::response::IntoResponse;
+
+use crate::{
+    cache::{self, CACHE_CAPACITY},
+    pages::{write_error, HtmlPage},
+};
+
+/// Shows what's in the cache.
+pub struct Cache;
+
+/// Empties the cache, then shows it.
+pub struct ClearCache;
+
+fn serve_cache_page(cleared: Option<usize>) -> crate::Result<HtmlPage> {
+    let mut page = HtmlPage::new().with_size_hint(2048)?;
+    page.insert_header()?;
+    writeln!(page, "<h1>Answer cache</h1><hr>")?;
+    if let Some(cleared) = cleared {
+        writeln!(page, "<p>Cleared {cleared} answers.</p>")?;
+    }
+    let (hits, misses) = cache::hits_and_misses();
+    let summaries = cache::summaries();
+    writeln!(
+        page,
+        "<p>{} of {CACHE_CAPACITY} answers kept, {hits} hits and {misses} misses since start up.</p>",
+        summaries.len()
+    )?;
+    writeln!(page, "<table>")?;
+    writeln!(page, "<tr><th>Day</th><th>Parts</th><th>Input hash</th><th>Time (ms)</th><th>Hits</th></tr>")?;
+    for summary in summaries.iter().rev() {
+        writeln!(
+            page,
+            "<tr><td>{}</td><td>{}</td><td>{:016x}</td><td>{}</td><td>{}</td></tr>",
+            summary.key.day,
+            summary.key.parts.as_str(),
+            summary.key.hash,
+            summary.elapsed.as_millis(),
+            summary.hits
+        )?;
+    }
+    writeln!(page, "</table>")?;
+    writeln!(page, r#"<form method="post"><input type="submit" value="Clear the cache"></form>"#)?;
+    page.insert_footer()?;
+    Ok(page)
+}
+
+async fn send_cache_page<
+    R: embedded_io_async::Read,
+    W: picoserve::response::ResponseWriter<Error = R::Error>,
+>(
+    cleared: Option<usize>,
+    r: picoserve::request::Request<'_, R>,
+    w: W,
+) -> Result<picoserve::ResponseSent, W::Error> {
+    match serve_cache_page(cleared) {
+        Ok(page) => {
+            info!("Cache page rendered, size {}", page.len());
+            page.write_to(r.body_connection.finalize().await?, w).await
+        },
+        Err(e) => {
+            error!("Error when trying to render cache page: {:?}", e);
+            write_error(&e, r.body_connection.finalize().await?, w).await
+        },
+    }
+}
+
+impl picoserve::routing::RequestHandlerService<()> for Cache {
+    async fn call_request_handler_service<
+        R: embedded_io_async::Read,
+        W: picoserve::response::ResponseWriter<Error = R::Error>,
+    >(
+        &self,
+        _state: &(),
+        _params: (),
+        r: picoserve::request::Request<'_, R>,
+        w: W,
+    ) -> Result<picoserve::ResponseSent, W::Error> {
+        send_cache_page(None, r, w).await
+    }
+}
+
+impl picoserve::routing::RequestHandlerService<()> for ClearCache {
+    async fn call_request_handler_service<
+        R: embedded_io_async::Read,
+        W: picoserve::response::ResponseWriter<Error = R::Error>,
+    >(
+        &self,
+        _state: &(),
+        _params: (),
+        r: picoserve::request::Request<'_, R>,
+        w: W,
+    ) -> Result<picoserve::ResponseSent, W::Error> {
+        let cleared = cache::clear();
+        info!("Cleared {} answers from the cache", cleared);
+        send_cache_page(Some(cleared), r, w).await
+    }
+}
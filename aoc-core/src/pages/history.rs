@@ -0,0 +1,96 @@
+use core::fmt::Write;
+
+use embassy_time::Instant;
+use picoserve::response::IntoResponse;
+
+use crate::{
+    answer::Value,
+    history::{self, Outcome, HISTORY_CAPACITY},
+    pages::{write_error, Escaped, HtmlPage, HtmlValue},
+};
+
+/// Shows the last few solves, newest first.
+pub struct History;
+
+/// A table cell for an answer, rendered as it is on the result page.
+fn write_value<W: Write>(w: &mut W, value: Option<&Value>) -> core::fmt::Result {
+    match value {
+        Some(v) => write!(w, "<td>{}</td>", HtmlValue(v)),
+        None => write!(w, "<td></td>"),
+    }
+}
+
+fn serve_history_page() -> crate::Result<HtmlPage> {
+    let runs = history::runs();
+    let mut page = HtmlPage::new().with_size_hint(4096)?;
+    page.insert_header()?;
+    writeln!(page, "<h1>Recent solves</h1><hr>")?;
+    writeln!(page, "<p>The last {HISTORY_CAPACITY} solves are kept, newest first.</p>")?;
+    writeln!(page, "<table>")?;
+    writeln!(
+        page,
+        "<tr><th>Ago (s)</th><th>Day</th><th>Parts</th><th>Input (bytes)</th><th>Input hash</th>\
+         <th>Part 1</th><th>Part 2</th><th>Time (ms)</th><th>Peak heap</th><th>Outcome</th></tr>"
+    )?;
+    let now = Instant::now();
+    for run in &runs {
+        write!(
+            page,
+            r#"<tr><td>{}</td><td><a href="/day/{day}">{day}</a></td><td>{}</td>"#,
+            now.saturating_duration_since(run.at).as_secs(),
+            run.parts.as_str(),
+            day = run.day,
+        )?;
+        match run.input_len {
+            Some(len) => write!(page, "<td>{len}</td>")?,
+            None => write!(page, "<td></td>")?,
+        }
+        match run.hash {
+            Some(hash) => write!(page, "<td>{hash:016x}</td>")?,
+            None => write!(page, "<td></td>")?,
+        }
+        let answer = match &run.outcome {
+            Outcome::Solved { answer, .. } => Some(answer),
+            Outcome::Failed { .. } => None,
+        };
+        write_value(&mut page, answer.and_then(|answer| answer.part1.as_ref())).map_err(|e| page.error(e))?;
+        write_value(&mut page, answer.and_then(|answer| answer.part2.as_ref())).map_err(|e| page.error(e))?;
+        write!(page, "<td>{}</td><td>{}</td>", run.elapsed.as_millis(), run.heap_peak)?;
+        match &run.outcome {
+            Outcome::Solved { cached: false, .. } => writeln!(page, "<td>solved</td></tr>")?,
+            Outcome::Solved { cached: true, .. } => writeln!(page, "<td>from the cache</td></tr>")?,
+            Outcome::Failed { code, message } => writeln!(
+                page,
+                r#"<td><span class="fail" title="{code}">{}</span></td></tr>"#,
+                Escaped(message)
+            )?,
+        }
+    }
+    writeln!(page, "</table>")?;
+    page.insert_footer()?;
+    Ok(page)
+}
+
+impl picoserve::routing::RequestHandlerService<()> for History {
+    async fn call_request_handler_service<
+        R: embedded_io_async::Read,
+        W: picoserve::response::ResponseWriter<Error = R::Error>,
+    >(
+        &self,
+        _state: &(),
+        _params: (),
+        r: picoserve::request::Request<'_, R>,
+        w: W,
+    ) -> Result<picoserve::ResponseSent, W::Error> {
+        match serve_history_page() {
+            Ok(page) => {
+                info!("History page rendered, size {}", page.len());
+                page.write_to(r.body_connection.finalize().await?, w).await
+            },
+            Err(e) => {
+                error!("Error when trying to render history page: {:?}", e);
+                write_error(&e, r.body_connection.finalize().await?, w).await
+            },
+        }
+    }
+}
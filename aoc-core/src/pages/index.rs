@@ -0,0 +1,74 @@
+use picoserve::response::IntoResponse;
+
+use crate::{
+    metrics::Counter,
+    pages::{write_error, HtmlPage},
+    problems::{self, Status},
+    selftest::{self, Health},
+};
+
+pub struct Index;
+
+static VIEWS: Counter =
+    Counter::new("aoc_index_views_total", "Times the index page has been served.");
+
+
+pub fn serve_index_page() -> crate::Result<HtmlPage> {
+    let mut page = HtmlPage::new()
+        .with_size_hint(2048)?;
+    page.insert_header()?;
+    writeln!(page, "<h1>Advent of Code Solver</h1><br><hr>")?;
+    writeln!(page, "Choose a day to solve:<ul>")?;
+    for day in problems::CALENDAR {
+        match problems::find(day) {
+            Some(entry) if selftest::health(day) == Health::Broken => writeln!(
+                page,
+                r#"<li><a href="/day/{day}">Day {day}: {}</a> <a class="fail" href="/selftest">(broken)</a></li>"#,
+                entry.title
+            )?,
+            Some(entry) if entry.status == Status::Implemented => writeln!(
+                page,
+                r#"<li><a href="/day/{day}">Day {day}: {}</a></li>"#,
+                entry.title
+            )?,
+            Some(entry) => writeln!(
+                page,
+                r#"<li><a href="/day/{day}">Day {day}: {}</a> (stub)</li>"#,
+                entry.title
+            )?,
+            None => writeln!(page, "<li>Day {day} (not implemented)</li>")?,
+        }
+    }
+    writeln!(page, "</ul><hr>")?;
+    writeln!(page, r#"<p>See what's been <a href="/history">solved recently</a>.</p>"#)?;
+    writeln!(page, "This page has been requested {} times", VIEWS.inc())?;
+    page.insert_footer()?;
+    Ok(page)
+}
+
+
+impl picoserve::routing::RequestHandlerService<()> for Index {
+    async fn call_request_handler_service<
+        R: embedded_io_async::Read,
+        W: picoserve::response::ResponseWriter<Error = R::Error>,
+    >(
+        &self,
+        _state: &(),
+        _params: (),
+        r: picoserve::request::Request<'_, R>,
+        w: W,
+    ) -> Result<picoserve::ResponseSent, W::Error> {
+        match serve_index_page() {
+            Ok(page) => {
+                info!("Index page rendered, size {}", page.len());
+                page.write_to(r.body_connection.finalize().await?, w)
+                    .await
+            }
+            Err(e) => {
+                error!("Error when trying to render index page: {:?}", e);
+                write_error(&e, r.body_connection.finalize().await?, w).await
+            },
+
+        }
+    }
+}
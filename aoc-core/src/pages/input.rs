@@ -0,0 +1,129 @@
+use core::fmt::Write;
+
+use picoserve::response::IntoResponse;
+
+use crate::{
+    pages::{write_error, Escaped, HtmlPage},
+    problems::{self, Day, Status},
+    storage,
+    Result,
+};
+
+// The options have to come before the textarea, only fields ahead of the input are looked at
+const FORM_DATA: &str = r#"<h2>Paste input into the box and hit submit:</h2>
+<form enctype="text/plain" method="post">
+<label for="part">Solve</label>
+<select name="part" id="part">
+<option value="both">both parts</option>
+<option value="1">part 1 only</option>
+<option value="2">part 2 only</option>
+</select><br>
+<label for="expect1">Expected answers, to check against:</label>
+<input name="expect1" id="expect1" placeholder="part 1">
+<input name="expect2" id="expect2" placeholder="part 2"><br>
+<label for="cache">Answer cache</label>
+<select name="cache" id="cache">
+<option value="on">solve, and cache the answer</option>
+<option value="lookup">use the cached answer if this input has been seen before</option>
+<option value="off">solve, and leave the cache and the saved input alone</option>
+</select><br>
+<textarea name="message" rows="20" cols="80"></textarea>
+<input type="submit">
+</form>
+"#;
+
+pub struct Input;
+
+/// A form per example, which sends it along with its expected answers, for a quick check that the
+/// solver is working. It's laid out the same as the form above, options first. Examples are run
+/// with `cache=off`, so they don't take the place of the day's saved input.
+fn write_examples<W: Write>(w: &mut W, entry: &Day) -> core::fmt::Result {
+    if entry.examples.is_empty() {
+        return Ok(());
+    }
+    writeln!(w, "<h2>Or try the example from the puzzle:</h2>")?;
+    for (n, example) in entry.examples.iter().enumerate() {
+        writeln!(w, r#"<form enctype="text/plain" method="post">"#)?;
+        writeln!(w, r#"<input type="hidden" name="part" value="{}">"#, example.parts().as_str())?;
+        for (name, expected) in [("expect1", example.part1), ("expect2", example.part2)] {
+            if let Some(expected) = expected {
+                writeln!(w, r#"<input type="hidden" name="{name}" value="{}">"#, Escaped(expected))?;
+            }
+        }
+        writeln!(w, r#"<input type="hidden" name="cache" value="off">"#)?;
+        let rows = example.input.lines().count().max(1);
+        writeln!(
+            w,
+            r#"<textarea name="message" rows="{rows}" cols="80" readonly>{}</textarea>"#,
+            Escaped(example.input)
+        )?;
+        if entry.examples.len() > 1 {
+            writeln!(w, r#"<input type="submit" value="Run example {}">"#, n + 1)?;
+        } else {
+            writeln!(w, r#"<input type="submit" value="Run example">"#)?;
+        }
+        writeln!(w, "</form>")?;
+    }
+    Ok(())
+}
+
+/// The input that was last solved for the day, if one was saved, with a button to solve it again.
+fn write_saved<W: Write>(w: &mut W, day: u32) -> core::fmt::Result {
+    let Some(saved) = storage::saved(day) else {
+        return Ok(());
+    };
+    writeln!(w, "<h2>Or re-run the last input:</h2>")?;
+    write!(w, "<p>{} bytes", saved.input_len)?;
+    for (part, answer) in [(1, &saved.part1), (2, &saved.part2)] {
+        if let Some(answer) = answer {
+            write!(w, ", part {part}: <code>{}</code>", Escaped(answer))?;
+        }
+    }
+    writeln!(w, "</p>")?;
+    writeln!(w, r#"<form method="post" action="/saved/{day}">"#)?;
+    writeln!(w, r#"<input type="submit" value="Re-run saved input">"#)?;
+    writeln!(w, "</form>")
+}
+
+fn serve_input_page(day: u32) -> Result<HtmlPage> {
+    let entry = problems::get(day)?;
+    let mut page = HtmlPage::new()
+        .with_size_hint(2048)?;
+    page.insert_header()?;
+    writeln!(page, "<h1>Advent of Code day {day}: {}</h1>", entry.title)?;
+    if entry.status == Status::Stub {
+        writeln!(page, "<p>This day is only a stub so far, it won't produce any answers.</p>")?;
+    }
+    writeln!(page, "<p>Inputs can be up to {} bytes.</p>", entry.input_limit())?;
+    page.write_str(FORM_DATA).map_err(|e| page.error(e))?;
+    write_examples(&mut page, entry).map_err(|e| page.error(e))?;
+    write_saved(&mut page, day).map_err(|e| page.error(e))?;
+    page.insert_footer()?;
+    Ok(page)
+}
+
+impl picoserve::routing::RequestHandlerService<(), (u32,)> for Input {
+    async fn call_request_handler_service<
+        R: embedded_io_async::Read,
+        W: picoserve::response::ResponseWriter<Error = R::Error>,
+    >(
+        &self,
+        _state: &(),
+        (day,): (u32,),
+        r: picoserve::request::Request<'_, R>,
+        w: W,
+    ) -> core::result::Result<picoserve::ResponseSent, W::Error> {
+        match serve_input_page(day) {
+            Ok(page) => {
+                info!("Index page rendered, size {}", page.len());
+                page.write_to(r.body_connection.finalize().await?, w)
+                    .await
+            }
+            Err(e) => {
+                error!("Error when trying to render input prompt: {:?}", e);
+                write_error(&e, r.body_connection.finalize().await?, w).await
+            },
+        }
+
+    }
+}
\ No newline at end of file
@@ -0,0 +1,46 @@
+use picoserve::response::IntoResponse;
+
+use crate::{
+    metrics,
+    pages::{write_error, ContentType, Page},
+};
+
+/// Every registered metric, in the Prometheus text exposition format.
+pub struct Metrics;
+
+/// The text exposition format, version 0.0.4 being the only one there is.
+struct Prometheus;
+
+impl ContentType for Prometheus {
+    const CONTENT_TYPE: &'static str = "text/plain; version=0.0.4";
+}
+
+fn serve_metrics_page() -> crate::Result<Page<Prometheus>> {
+    let mut page = Page::new().with_size_hint(4096)?;
+    metrics::expose(&mut page).map_err(|e| page.error(e))?;
+    Ok(page)
+}
+
+impl picoserve::routing::RequestHandlerService<()> for Metrics {
+    async fn call_request_handler_service<
+        R: embedded_io_async::Read,
+        W: picoserve::response::ResponseWriter<Error = R::Error>,
+    >(
+        &self,
+        _state: &(),
+        _params: (),
+        r: picoserve::request::Request<'_, R>,
+        w: W,
+    ) -> Result<picoserve::ResponseSent, W::Error> {
+        match serve_metrics_page() {
+            Ok(page) => {
+                debug!("Metrics rendered, size {}", page.len());
+                page.write_to(r.body_connection.finalize().await?, w).await
+            },
+            Err(e) => {
+                error!("Error when trying to render metrics: {:?}", e);
+                write_error(&e, r.body_connection.finalize().await?, w).await
+            },
+        }
+    }
+}
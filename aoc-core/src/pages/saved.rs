@@ -0,0 +1,45 @@
+use picoserve::response::IntoResponse;
+
+use crate::{
+    helpers::InputFormat,
+    pages::{do_problem, write_error, SolveOptions},
+    storage::{self, SavedInput},
+};
+
+/// Solves a day again from the input saved for it, for the same parts unless the query string says
+/// otherwise.
+pub struct RunSaved;
+
+impl picoserve::routing::RequestHandlerService<(), (u32,)> for RunSaved {
+    async fn call_request_handler_service<
+        R: embedded_io_async::Read,
+        W: picoserve::response::ResponseWriter<Error = R::Error>,
+    >(
+        &self,
+        _state: &(),
+        (day,): (u32,),
+        r: picoserve::request::Request<'_, R>,
+        w: W,
+    ) -> core::result::Result<picoserve::ResponseSent, W::Error> {
+        info!("Re-running day {}'s saved input", day);
+        let page = match (SolveOptions::parse(r.parts.query().unwrap_or_default()), SavedInput::open(day)) {
+            (Ok(mut options), Ok(input)) => {
+                options.part = options.part.or(storage::saved(day).map(|saved| saved.parts));
+                options.saved = true;
+                let len = input.len();
+                do_problem(input, InputFormat::Raw, day, len, options).await
+            },
+            (Err(e), _) | (_, Err(e)) => Err(e),
+        };
+        match page {
+            Ok(page) => {
+                info!("Problem complete, Response size {}", page.len());
+                page.write_to(r.body_connection.finalize().await?, w).await
+            },
+            Err(e) => {
+                error!("Error when re-running saved input: {:?}", e);
+                write_error(&e, r.body_connection.finalize().await?, w).await
+            },
+        }
+    }
+}
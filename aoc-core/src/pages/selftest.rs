@@ -0,0 +1,66 @@
+use picoserve::response::{IntoResponse, StatusCode};
+
+use crate::{
+    pages::{write_error, HtmlPage},
+    problems::{self, Status},
+    selftest::{self, Health},
+};
+
+pub struct SelfTest;
+
+/// How each day did in the boot self-test. Sent as a 500 if any of them are broken, so it can be
+/// checked with nothing more than the status.
+pub fn serve_self_test_page() -> crate::Result<HtmlPage> {
+    let mut page = HtmlPage::new().with_size_hint(2048)?;
+    page.insert_header()?;
+    writeln!(page, "<h1>Self-test</h1><hr>")?;
+    writeln!(page, "<p>Every implemented day is run over the examples from its puzzle at start up.</p>")?;
+    writeln!(page, "<table>")?;
+    writeln!(page, "<tr><th>Day</th><th>Examples</th><th>Result</th></tr>")?;
+    for entry in problems::DAYS {
+        let result = match (entry.status, selftest::health(entry.day)) {
+            (Status::Stub, _) => "stub",
+            (_, Health::Untested) => "not run yet",
+            (_, Health::Passed) => r#"<span class="pass">passed</span>"#,
+            (_, Health::Broken) => r#"<span class="fail">broken</span>"#,
+        };
+        writeln!(
+            page,
+            r#"<tr><td><a href="/day/{0}">Day {0}: {1}</a></td><td>{2}</td><td>{result}</td></tr>"#,
+            entry.day,
+            entry.title,
+            entry.examples.len()
+        )?;
+    }
+    writeln!(page, "</table>")?;
+    if !selftest::all_passed() {
+        page.set_status(StatusCode::INTERNAL_SERVER_ERROR);
+        writeln!(page, "<p>Broken days have logged what they got wrong over the serial port.</p>")?;
+    }
+    page.insert_footer()?;
+    Ok(page)
+}
+
+impl picoserve::routing::RequestHandlerService<()> for SelfTest {
+    async fn call_request_handler_service<
+        R: embedded_io_async::Read,
+        W: picoserve::response::ResponseWriter<Error = R::Error>,
+    >(
+        &self,
+        _state: &(),
+        _params: (),
+        r: picoserve::request::Request<'_, R>,
+        w: W,
+    ) -> Result<picoserve::ResponseSent, W::Error> {
+        match serve_self_test_page() {
+            Ok(page) => {
+                info!("Self-test page rendered, size {}", page.len());
+                page.write_to(r.body_connection.finalize().await?, w).await
+            },
+            Err(e) => {
+                error!("Error when trying to render self-test page: {:?}", e);
+                write_error(&e, r.body_connection.finalize().await?, w).await
+            },
+        }
+    }
+}
@@ -0,0 +1,354 @@
+use alloc::{string::String, vec::Vec};
+
+use picoserve::{
+    response::{IntoResponse, StatusCode},
+    url_encoded::{deserialize_form, UrlEncodedString},
+};
+use serde::Deserialize;
+
+use embassy_time::{Duration, Instant};
+
+use crate::{
+    answer::{Answer, Checks},
+    cache::{self, CacheMode, Cached, Key},
+    error::AerError,
+    helpers::{read_input, InputFormat, InputReader},
+    history::{self, Outcome},
+    metrics::Family,
+    mem::{HeapUsage, HeapWatch},
+    pages::{do_bench, write_answer, write_error, write_timings, HtmlPage},
+    problems::{self, Context, Parts},
+    stack::{StackUsage, StackWatch},
+    storage,
+    timing::{Phase, Timings},
+    Result,
+};
+
+/// Solve `day` for the input in `r` and render the result page.
+/// A failed solve still gets a page, sent with the error's status. Unknown days are an error.
+pub async fn do_problem<R: picoserve::io::Read>(
+    r: R,
+    format: InputFormat,
+    day: u32,
+    input_len: usize,
+    options: SolveOptions,
+) -> Result<HtmlPage> {
+    info!("Doing day {}", day);
+    let entry = problems::get(day)?;
+    let mut page = HtmlPage::new().with_size_hint(2048)?;
+    page.insert_header()?;
+    writeln!(page, "<h1>Advent of Code day {day}: {}</h1><hr>", entry.title)?;
+    writeln!(page, "<code>")?;
+    info!("Problem Start");
+    let mut run = run_problem(r, format, day, input_len, options).await;
+    match &run.result {
+        Ok(answer) => {
+            let checks = run.options.checks(answer);
+            if !checks.passed() {
+                page.set_status(CHECK_FAILED);
+            }
+            write_answer(&mut page, answer, run.options.parts(), &checks).map_err(|e| page.error(e))?;
+        },
+        Err(e) => {
+            page.set_status(e.status());
+            writeln!(page, "<br>Encountered error: {e}")?;
+        },
+    }
+    if run.cached {
+        writeln!(page, "Answered from the cache, the figures are from when it was first solved<br>")?;
+    }
+    write!(page, "Evaluated in {}ms", run.elapsed.as_millis())?;
+    if let Some(StackUsage { used, free }) = run.stack {
+        write!(page, ", {used} bytes of stack used ({free} to spare)")?;
+    }
+    writeln!(page, "<br>")?;
+    let HeapUsage { before, peak, after } = run.heap;
+    writeln!(page, "Heap used: {before} bytes before, {peak} at peak, {after} after")?;
+    writeln!(page, r"</code>")?;
+    run.timings.mark(Phase::Render);
+    write_timings(&mut page, &run.timings).map_err(|e| page.error(e))?;
+    page.insert_footer()?;
+    Ok(page)
+}
+
+/// Everything that came out of one solve.
+pub struct Run {
+    pub result: Result<Answer>,
+    /// As finally decided, form fields included.
+    pub options: SolveOptions,
+    pub elapsed: Duration,
+    pub heap: HeapUsage,
+    /// Only measured on the board.
+    pub stack: Option<StackUsage>,
+    /// Where the time went. Adds up to [`Run::elapsed`], until the page puts its render time in.
+    pub timings: Timings,
+    /// Whether the answer came from the cache. If so, the figures are from the original solve.
+    pub cached: bool,
+}
+
+impl Run {
+    /// A solve that never got started.
+    pub fn failed(e: AerError) -> Self {
+        Self {
+            result: Err(e),
+            options: SolveOptions::default(),
+            elapsed: Duration::MIN,
+            heap: HeapUsage::default(),
+            stack: None,
+            timings: Timings::new(),
+            cached: false,
+        }
+    }
+}
+
+/// [`lookup_problem`], timed and with an eye on the heap and stack.
+pub async fn run_problem<R: picoserve::io::Read>(
+    r: R,
+    format: InputFormat,
+    day: u32,
+    input_len: usize,
+    mut options: SolveOptions,
+) -> Run {
+    // Painting the stack takes a moment, keep it out of the timing
+    let stack_watch = StackWatch::start();
+    let heap_watch = HeapWatch::start();
+    let mut cx = Context::new();
+    let (result, solved) = match lookup_problem(r, format, day, input_len, &mut options, &mut cx).await {
+        Ok(Lookup::Solved { answer, key, input_len, input }) => (Ok(answer), Some((key, input_len, input))),
+        Ok(Lookup::Cached { key, input_len, cached: Cached { answer, elapsed, heap, stack, timings } }) => {
+            info!("Day {} answered from the cache", day);
+            let mut run = Run { result: Ok(answer), options, elapsed, heap, stack, timings, cached: true };
+            record(&run, day, Some((input_len, key.hash)));
+            // Rendering still has to be timed, from here
+            run.timings.skip();
+            return run;
+        },
+        Err(e) => (Err(e), None),
+    };
+    let mut timings = cx.timings;
+    timings.mark(Phase::Other);
+    let elapsed = timings.elapsed();
+    let heap = heap_watch.finish();
+    let stack = stack_watch.finish();
+    info!(
+        "Day {} took {}ms, heap peaked at {} bytes ({} before, {} after)",
+        day,
+        elapsed.as_millis(),
+        heap.peak,
+        heap.before,
+        heap.after
+    );
+    if let Some(stack) = stack {
+        info!("Day {} used {} bytes of stack, {} to spare", day, stack.used, stack.free);
+    }
+    let input = solved.as_ref().map(|(key, input_len, _)| (*input_len, key.hash));
+    if let (Ok(answer), Some((key, _, input))) = (&result, solved) {
+        if options.cache.unwrap_or_default() != CacheMode::Off {
+            match answer.try_clone() {
+                Ok(answer) => {
+                    let timings = timings.clone();
+                    cache::insert(key, Cached { answer, elapsed, heap, stack, timings });
+                },
+                Err(_) => warn!("No room to cache day {}'s answer", day),
+            }
+        }
+        if let Some(input) = input {
+            storage::queue(day, key.hash, key.parts, answer, input);
+        }
+    }
+    let mut run = Run { result, options, elapsed, heap, stack, timings, cached: false };
+    record(&run, day, input);
+    // Checking the stack paint, logging, caching, queueing the save and recording aren't part of
+    // rendering the page either
+    run.timings.skip();
+    run
+}
+
+/// Fresh solves, and how long they took, by day. Room for every day in the calendar.
+static SOLVES: Family<25> =
+    Family::timer("aoc_solve_duration_seconds", "Time taken by solves by day, not counting cached answers.");
+
+/// Put `run` in the history and the metrics. `input` is the input's size and hash, if it got as far
+/// as being read. Days that aren't in the calendar have no input page for the history to link to,
+/// so they're left out.
+fn record(run: &Run, day: u32, input: Option<(usize, u64)>) {
+    if !problems::CALENDAR.contains(&day) {
+        return;
+    }
+    if !run.cached {
+        SOLVES.observe(format_args!(r#"day="{day}""#), run.elapsed);
+    }
+    history::record(history::Run {
+        at: Instant::now(),
+        day,
+        parts: run.options.parts(),
+        input_len: input.map(|(len, _)| len),
+        hash: input.map(|(_, hash)| hash),
+        outcome: Outcome::from_result(&run.result, run.cached),
+        elapsed: run.elapsed,
+        heap_peak: run.heap.peak,
+    });
+}
+
+/// What [`lookup_problem`] came up with.
+pub enum Lookup {
+    /// Solved just now. The answer belongs under `key` in the cache, and `input` is there if it's
+    /// to be saved. `input_len` is the size of the input itself, without any form fields.
+    Solved { answer: Answer, key: Key, input_len: usize, input: Option<Vec<u8>> },
+    /// Found in the cache under `key`.
+    Cached { key: Key, input_len: usize, cached: Cached },
+}
+
+/// Wrap the request body in an [`InputReader`] and hand it over to the solver for `day`.
+/// Inputs over the day's size limit are turned away, before the input is read if the body says
+/// so, see [`InputReader::limit`]. Form fields ahead of the input are merged into `options`,
+/// which then decide what goes in `cx`.
+///
+/// Normally the solver gets the input as it arrives, and it's hashed on the way through. Whatever
+/// the solver leaves unread is read afterwards so the hash covers all of it. With `cache=lookup`
+/// the whole input is read in first instead, so that it can be looked up in the cache before
+/// solving. If there isn't room for it, it's solved as it arrives after all.
+///
+/// If there's storage installed, a copy of inputs up to [`storage::SAVE_LIMIT`] is kept for
+/// saving, which costs that much more heap during the solve. Re-runs of the saved input aren't
+/// saved again, and neither is anything solved with `cache=off`.
+pub async fn lookup_problem<R: picoserve::io::Read>(
+    r: R,
+    format: InputFormat,
+    day: u32,
+    input_len: usize,
+    options: &mut SolveOptions,
+    cx: &mut Context,
+) -> Result<Lookup> {
+    let entry = problems::get(day)?;
+    let mut reader = InputReader::new(r, format, input_len).await?;
+    reader.limit(entry.input_limit())?;
+    options.merge(SolveOptions::parse(reader.form_fields())?);
+    cx.parts = options.parts();
+    let size_hint = reader.size_hint();
+    let save = storage::installed() && !options.saved && options.cache != Some(CacheMode::Off);
+    if options.cache == Some(CacheMode::Lookup) {
+        // The buffer is allocated before anything's read, so if there's no room for it the reader
+        // is still at the start of the input
+        match read_input(&mut reader, size_hint).await {
+            Ok(input) => {
+                let key = Key { day, parts: cx.parts, hash: reader.finish().await? };
+                cx.timings.mark(Phase::Read);
+                if let Some(cached) = cache::get(&key) {
+                    return Ok(Lookup::Cached { key, input_len: input.len(), cached });
+                }
+                let answer = problems::solve(day, &mut input.as_bytes(), input.len(), cx).await?;
+                let input_len = input.len();
+                let input = save.then(|| input.into_bytes());
+                return Ok(Lookup::Solved { answer, key, input_len, input });
+            },
+            Err(AerError::OutOfMemory { needed, free }) => {
+                warn!("No room to keep the input ({} bytes, {} free), solving as it arrives", needed, free);
+            },
+            Err(e) => return Err(e),
+        }
+    }
+    if save {
+        reader.keep_copy(storage::SAVE_LIMIT);
+    }
+    let answer = problems::solve(day, &mut reader, size_hint, cx).await;
+    // An input over the limit is cut short, which the solver may have made anything of
+    let key = Key { day, parts: cx.parts, hash: reader.finish().await? };
+    let answer = answer?;
+    Ok(Lookup::Solved { answer, key, input_len: reader.input_len(), input: reader.take_copy() })
+}
+
+/// What a solve whose answers don't match the expected ones is sent with, so that scripts can tell.
+pub const CHECK_FAILED: StatusCode = StatusCode::UNPROCESSABLE_ENTITY;
+
+/// Options for a solve. They come from the query string, or from form fields ahead of the input,
+/// which win if both are given.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct SolveOptions {
+    /// Which parts to solve, both unless told otherwise.
+    pub part: Option<Parts>,
+    /// Solve this many times over and show timing figures, rather than solving once. Only from
+    /// the query string, as it has to be known before the body is read.
+    pub bench: Option<u32>,
+    /// Expected answer for part 1, to check the solver against.
+    pub expect1: Option<String>,
+    /// Expected answer for part 2.
+    pub expect2: Option<String>,
+    /// `lookup` to answer from the cache if the input's been solved before, `off` to leave the
+    /// cache and the saved input alone.
+    pub cache: Option<CacheMode>,
+    /// Set for re-runs of the saved input, which has nothing new to save. Never from a request.
+    #[serde(skip)]
+    pub saved: bool,
+}
+
+impl SolveOptions {
+    /// Parse a query string, or form fields. Anything unknown is ignored, and so are empty expected
+    /// answers, which is what the input form sends when they're left blank.
+    pub fn parse(fields: UrlEncodedString<'_>) -> Result<Self> {
+        let mut options: Self = deserialize_form(fields).map_err(|_| AerError::BadQuery)?;
+        options.expect1 = options.expect1.filter(|e| !e.trim().is_empty());
+        options.expect2 = options.expect2.filter(|e| !e.trim().is_empty());
+        Ok(options)
+    }
+
+    /// Take on every option that's set in `other`.
+    pub fn merge(&mut self, other: Self) {
+        self.part = other.part.or(self.part);
+        self.expect1 = other.expect1.or(self.expect1.take());
+        self.expect2 = other.expect2.or(self.expect2.take());
+        self.cache = other.cache.or(self.cache);
+    }
+
+    pub fn parts(&self) -> Parts {
+        self.part.unwrap_or_default()
+    }
+
+    /// Check `answer` against the expected answers, for the parts that were solved.
+    pub fn checks(&self, answer: &Answer) -> Checks {
+        let parts = self.parts();
+        let expect1 = self.expect1.as_deref().filter(|_| parts.part1());
+        let expect2 = self.expect2.as_deref().filter(|_| parts.part2());
+        Checks::new(answer, expect1, expect2)
+    }
+}
+
+pub struct Solver;
+impl picoserve::routing::RequestHandlerService<(), (u32,)> for Solver {
+    async fn call_request_handler_service<
+        R: embedded_io_async::Read,
+        W: picoserve::response::ResponseWriter<Error = R::Error>,
+    >(
+        &self,
+        _state: &(),
+        (day,): (u32,),
+        mut r: picoserve::request::Request<'_, R>,
+        w: W,
+    ) -> core::result::Result<picoserve::ResponseSent, W::Error> {
+        let content_length = r.body_connection.content_length();
+        let format = InputFormat::from_content_type(
+            r.parts.headers().get("Content-Type").map(|v| v.as_raw()),
+        );
+        info!("Doing problem {}, input length {}, format {}", day, content_length, format);
+        let page = match SolveOptions::parse(r.parts.query().unwrap_or_default()) {
+            Ok(options) => {
+                let body = r.body_connection.body().reader();
+                match options.bench {
+                    Some(runs) => do_bench(body, format, day, content_length, runs, options).await,
+                    None => do_problem(body, format, day, content_length, options).await,
+                }
+            },
+            Err(e) => Err(e),
+        };
+        match page {
+            Ok(page) => {
+                info!("Problem complete, Response size {}", page.len());
+                page.write_to(r.body_connection.finalize().await?, w).await
+            },
+            Err(e) => {
+                error!("Error when doing problem: {:?}", e);
+                write_error(&e, r.body_connection.finalize().await?, w).await
+            },
+        }
+    }
+}
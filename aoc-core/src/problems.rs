@@ -0,0 +1,248 @@
+//! Just the top level module for the problem sources.
+//!
+//! Each day lives in its own module and implements [`Solver`]. To add a day, add the module below
+//! and a line to the `days!` registry at the bottom of this file. The index page, the input pages
+//! and the solver route all work from the registry, so nothing else needs touching. The puzzle's
+//! examples go in [`Solver::EXAMPLES`], where they're offered on the input page and checked by the
+//! self-test in [`crate::selftest`].
+
+use core::ops::RangeInclusive;
+
+use picoserve::io::Read;
+use serde::Deserialize;
+
+use crate::{
+    answer::{Answer, Value},
+    error::AerError,
+    timing::Timings,
+    Result,
+};
+
+pub mod p01;
+pub mod p02;
+pub mod p03;
+
+/// Every day in the advent calendar, whether or not we have a solver for it.
+pub const CALENDAR: RangeInclusive<u32> = 1..=25;
+
+/// The most input any day will accept, in bytes. Real inputs are all well under this, and the
+/// limit is checked before anything is allocated so a silly `Content-Length` can't eat the heap.
+pub const MAX_INPUT_SIZE: usize = 32 * 1024;
+
+/// How far along the solver for a given day is.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// Produces answers for both parts.
+    Implemented,
+    /// The module exists but it doesn't solve anything yet.
+    Stub,
+}
+
+/// Which parts of a puzzle to solve.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum Parts {
+    #[serde(rename = "1")]
+    One,
+    #[serde(rename = "2")]
+    Two,
+    #[default]
+    #[serde(rename = "both")]
+    Both,
+}
+
+impl Parts {
+    pub fn part1(self) -> bool {
+        self != Parts::Two
+    }
+
+    pub fn part2(self) -> bool {
+        self != Parts::One
+    }
+
+    /// As it's written in the query string and form.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Parts::One => "1",
+            Parts::Two => "2",
+            Parts::Both => "both",
+        }
+    }
+
+    /// An answer with just the wanted parts, for days that work out both either way.
+    pub fn answer(self, part1: impl Into<Value>, part2: impl Into<Value>) -> Answer {
+        let mut answer = Answer::new();
+        if self.part1() {
+            answer = answer.with_part1(part1);
+        }
+        if self.part2() {
+            answer = answer.with_part2(part2);
+        }
+        answer
+    }
+}
+
+/// `1`, `2` or `both`, as in the query string and form.
+impl core::str::FromStr for Parts {
+    type Err = ();
+
+    fn from_str(s: &str) -> core::result::Result<Self, ()> {
+        match s {
+            "1" => Ok(Parts::One),
+            "2" => Ok(Parts::Two),
+            "both" => Ok(Parts::Both),
+            _ => Err(()),
+        }
+    }
+}
+
+/// One of the small example inputs from a puzzle's description, with the answers it gives there.
+#[derive(Clone, Copy, Debug)]
+pub struct Example {
+    pub input: &'static str,
+    /// `None` where the puzzle doesn't give an answer for that part, e.g. when each part comes with
+    /// its own example.
+    pub part1: Option<&'static str>,
+    pub part2: Option<&'static str>,
+}
+
+impl Example {
+    /// The parts this example has answers for.
+    pub fn parts(&self) -> Parts {
+        match (self.part1, self.part2) {
+            (Some(_), None) => Parts::One,
+            (None, Some(_)) => Parts::Two,
+            _ => Parts::Both,
+        }
+    }
+}
+
+/// Everything a solver gets handed besides its input.
+#[derive(Default)]
+pub struct Context {
+    /// Solvers mark their phases on this as they go, see [`crate::timing`].
+    pub timings: Timings,
+    /// Solvers can skip the work for parts that aren't wanted, and should leave them out of the
+    /// answer.
+    pub parts: Parts,
+}
+
+impl Context {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn with_parts(mut self, parts: Parts) -> Self {
+        self.parts = parts;
+        self
+    }
+}
+
+/// A single day's puzzle.
+// Solvers only ever run on our own single threaded executors, so there's no need for `Send` bounds
+#[allow(async_fn_in_trait)]
+pub trait Solver {
+    /// Day of the advent calendar, `1..=25`.
+    const DAY: u32;
+    /// Puzzle title, as it appears on the Advent of Code site.
+    const TITLE: &'static str;
+    /// Defaults to implemented, stubs should override it.
+    const STATUS: Status = Status::Implemented;
+    /// Largest input this day accepts, in bytes. Solvers whose memory use grows with the input
+    /// should set this lower. Capped at [`MAX_INPUT_SIZE`] either way.
+    const MAX_INPUT: usize = MAX_INPUT_SIZE;
+    /// The examples from the puzzle description, offered on the input page.
+    const EXAMPLES: &'static [Example] = &[];
+
+    /// Read the puzzle input from `r` and work out the answers.
+    /// `r` produces just the input, form prefixes and encoding have already been dealt with.
+    /// `input_len` is an upper bound on the size of the input.
+    async fn solve<R: Read>(r: &mut R, input_len: usize, cx: &mut Context) -> Result<Answer>;
+}
+
+/// Registry entry for a day. Everything about a [`Solver`] except the entry point itself.
+#[derive(Clone, Copy)]
+pub struct Day {
+    pub day: u32,
+    pub title: &'static str,
+    pub status: Status,
+    pub max_input: usize,
+    pub examples: &'static [Example],
+}
+
+impl Day {
+    /// Largest input this day accepts, in bytes.
+    pub fn input_limit(&self) -> usize {
+        self.max_input.min(MAX_INPUT_SIZE)
+    }
+}
+
+/// Look up the registry entry for `day`. `None` means we have no module for that day.
+pub fn find(day: u32) -> Option<&'static Day> {
+    DAYS.iter().find(|d| d.day == day)
+}
+
+/// Like [`find`], but with the error to report when there's no module for `day`.
+pub fn get(day: u32) -> Result<&'static Day> {
+    find(day).ok_or_else(|| no_solver(day))
+}
+
+/// Why there's no solver for `day`: either it's not written yet, or it isn't in the calendar at all.
+fn no_solver(day: u32) -> AerError {
+    if CALENDAR.contains(&day) {
+        AerError::Unimplemented { day }
+    } else {
+        AerError::BadDay { day }
+    }
+}
+
+/// Builds the static registry and the dispatch function from a list of solvers.
+/// The async solve functions can't go in a table of trait objects, hence the macro.
+macro_rules! days {
+    ($($solver:ty),* $(,)?) => {
+        /// Every day that has a module, in calendar order.
+        pub const DAYS: &[Day] = &[
+            $(Day {
+                day: <$solver as Solver>::DAY,
+                title: <$solver as Solver>::TITLE,
+                status: <$solver as Solver>::STATUS,
+                max_input: <$solver as Solver>::MAX_INPUT,
+                examples: <$solver as Solver>::EXAMPLES,
+            },)*
+        ];
+
+        /// Run the solver registered for `day`.
+        pub async fn solve<R: Read>(
+            day: u32,
+            r: &mut R,
+            input_len: usize,
+            cx: &mut Context,
+        ) -> Result<Answer> {
+            $(
+                if day == <$solver as Solver>::DAY {
+                    return <$solver as Solver>::solve(r, input_len, cx).await;
+                }
+            )*
+            Err(no_solver(day))
+        }
+    };
+}
+
+days! {
+    p01::Day01,
+    p02::Day02,
+    p03::Day03,
+}
+
+/// Run a solver over an example input, for unit tests.
+#[cfg(test)]
+pub(crate) fn solve_example<S: Solver>(input: &str) -> Answer {
+    solve_example_parts::<S>(input, Parts::Both)
+}
+
+/// Like [`solve_example`], for only some of the parts.
+#[cfg(test)]
+pub(crate) fn solve_example_parts<S: Solver>(input: &str, parts: Parts) -> Answer {
+    let mut cx = Context::new().with_parts(parts);
+    embassy_futures::block_on(S::solve(&mut input.as_bytes(), input.len(), &mut cx)).unwrap()
+}
@@ -0,0 +1,96 @@
+use alloc::vec::Vec;
+use picoserve::io::Read;
+
+use crate::{
+    answer::Answer,
+    helpers::LineReader,
+    mem::{try_vec, TryPush},
+    problems::{Context, Example, Solver},
+    timing::Phase,
+};
+
+/// Each line is two five digit numbers separated by three spaces
+const LINE_LENGTH: usize = "12345   12345\n".len();
+
+const EXAMPLE: &str = "3   4\n4   3\n2   5\n1   3\n3   9\n3   3\n";
+
+/// day 1: Historian's Location IDs
+pub struct Day01;
+
+impl Solver for Day01 {
+    const DAY: u32 = 1;
+    const TITLE: &'static str = "Historian Hysteria";
+    // Both columns end up in memory. Real inputs are 1000 lines, about 14 KB.
+    const MAX_INPUT: usize = 16 * 1024;
+    const EXAMPLES: &'static [Example] = &[Example {
+        input: EXAMPLE,
+        part1: Some("11"),
+        part2: Some("31"),
+    }];
+
+    async fn solve<R: Read>(r: &mut R, input_len: usize, cx: &mut Context) -> crate::Result<Answer> {
+        let line_count = input_len / LINE_LENGTH;
+        let mut left_numbers: Vec<i32> = try_vec(line_count)?;
+        let mut right_numbers: Vec<i32> = try_vec(line_count)?;
+        let mut lines = LineReader::<_, 64>::new(r);
+        while let Some(line) = lines.next_line().await? {
+            cx.timings.mark(Phase::Read);
+            if let Some((l, r)) = line.split_once("   ") {
+                left_numbers.try_push(l.parse()?)?;
+                right_numbers.try_push(r.parse()?)?;
+            }
+            cx.timings.mark(Phase::Parse);
+        }
+        cx.timings.mark(Phase::Read);
+        left_numbers.sort_unstable();
+        right_numbers.sort_unstable();
+        cx.timings.mark(Phase::Parse);
+        let mut answer = Answer::new();
+        if cx.parts.part1() {
+            let p1_answer: u32 = left_numbers.iter()
+                .zip(right_numbers.iter())
+                .map(|(l, r)| l.abs_diff(*r))
+                .sum();
+            answer = answer.with_part1(p1_answer);
+            cx.timings.mark(Phase::Part1);
+        }
+        if cx.parts.part2() {
+            let mut p2_answer: usize = 0;
+            for l in &left_numbers {
+                p2_answer += l.unsigned_abs() as usize * right_numbers.iter()
+                    .take_while(|r| *r <= l) // The array is sorted and it's marginally faster to solve this way
+                    .filter(|r| *r == l)
+                    .count();
+            }
+            answer = answer.with_part2(p2_answer);
+            cx.timings.mark(Phase::Part2);
+        }
+        Ok(answer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Day01, EXAMPLE};
+    use crate::{
+        answer::Value,
+        problems::{solve_example, solve_example_parts, Parts},
+    };
+
+    #[test]
+    fn example() {
+        let answer = solve_example::<Day01>(EXAMPLE);
+        assert_eq!(answer.part1, Some(Value::UInt(11)));
+        assert_eq!(answer.part2, Some(Value::UInt(31)));
+    }
+
+    #[test]
+    fn single_parts() {
+        let answer = solve_example_parts::<Day01>(EXAMPLE, Parts::One);
+        assert_eq!(answer.part1, Some(Value::UInt(11)));
+        assert_eq!(answer.part2, None);
+        let answer = solve_example_parts::<Day01>(EXAMPLE, Parts::Two);
+        assert_eq!(answer.part1, None);
+        assert_eq!(answer.part2, Some(Value::UInt(31)));
+    }
+}
@@ -0,0 +1,150 @@
+use core::str;
+
+use alloc::vec::Vec;
+use picoserve::io::Read;
+
+use crate::{
+    answer::Answer,
+    helpers::LineReader,
+    mem::{try_vec, TryPush},
+    problems::{Context, Example, Parts, Solver},
+    timing::Phase,
+};
+
+/// Helper trait that creates an iterator of sequential pairs, but where the Nth item of the input 
+/// slice is skipped.
+trait SkipNZip {
+    type Item;
+    fn skip_n_zip(self, skip: usize) -> impl Iterator<Item = Self::Item>;
+}
+
+impl<'a, T: Sized> SkipNZip for &'a [T] {
+    type Item = (&'a T, &'a T);
+
+    fn skip_n_zip(self, skip: usize) -> impl Iterator<Item = Self::Item> {
+        // there's probably a better way to do this
+        let a = self
+            .iter()
+            .enumerate()
+            .filter(move |(n, _)| *n != skip)
+            .map(|(_, n)| n);
+        let b = self
+            .iter()
+            .enumerate()
+            .filter(move |(n, _)| *n != skip)
+            .map(|(_, n)| n)
+            .skip(1);
+        a.zip(b)
+    }
+}
+
+/// For a given pair of reports, what is their class?
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum ReportClass {
+    /// Unsafe - `delta := {0, >3, <-3}`
+    Unsafe,
+    /// Safe ascending - `delta := {1..=3}`
+    SafeAsc,
+    /// Safe descending - `delta := {-3..=-1}`
+    SafeDesc,
+}
+
+/// Classify pairs of numbers into their class, presented as `ReportClass`
+fn classify_pair((l, r): (&i16, &i16)) -> ReportClass {
+    match l - r {
+        1..=3 => ReportClass::SafeAsc,
+        -3..=-1 => ReportClass::SafeDesc,
+        _ => ReportClass::Unsafe,
+    }
+}
+
+/// Reports in the real inputs have at most 8 levels.
+const REPORT_CAPACITY: usize = 8;
+
+/// Function designed for folding, retains counters of all of the p1-safe and p2-safe report strings
+/// for a single input. The p2 counter is left alone if `parts` doesn't want part 2, as that's the
+/// expensive bit.
+/// We parse the input a line at a time because we don't have enough memory to parse the whole
+/// input into a `Vec<Vec<i16>>` all at once, or even to hold the whole input.
+fn fold_safe_reports(
+    (p1, p2): (u32, u32),
+    report: &str,
+    parts: Parts,
+) -> crate::Result<(u32, u32)> {
+    let mut reports: Vec<i16> = try_vec(REPORT_CAPACITY)?;
+    for level in report.split(' ') {
+        let Ok(level) = level.parse() else {
+            return Ok((p1, p2));
+        };
+        reports.try_push(level)?;
+    }
+
+    if reports
+        .iter()
+        .zip(reports.iter().skip(1))
+        .map(classify_pair)
+        .reduce(|a, b| if a == b { a } else { ReportClass::Unsafe })
+        .is_some_and(|v| v != ReportClass::Unsafe)
+    {
+        return Ok((p1 + 1, p2 + 1));
+    }
+    if !parts.part2() {
+        return Ok((p1, p2));
+    }
+
+    for i in 0..reports.len() {
+        if reports
+            .skip_n_zip(i)
+            .map(classify_pair)
+            .reduce(|a, b| if a == b { a } else { ReportClass::Unsafe })
+            .is_some_and(|v| v != ReportClass::Unsafe)
+        {
+            return Ok((p1, p2 + 1));
+        }
+    }
+    Ok((p1, p2))
+}
+
+const EXAMPLE: &str = "7 6 4 2 1\r\n1 2 7 8 9\r\n9 7 6 2 1\r\n1 3 2 4 5\r\n8 6 4 4 1\r\n1 3 6 7 9";
+
+/// day 2: Reactor safety reports
+pub struct Day02;
+
+impl Solver for Day02 {
+    const DAY: u32 = 2;
+    const TITLE: &'static str = "Red-Nosed Reports";
+    const EXAMPLES: &'static [Example] = &[Example {
+        input: EXAMPLE,
+        part1: Some("2"),
+        part2: Some("4"),
+    }];
+
+    async fn solve<R: Read>(r: &mut R, input_len: usize, cx: &mut Context) -> crate::Result<Answer> {
+        info!("Solving for input of size {}", input_len);
+        let mut lines = LineReader::<_, 64>::new(r);
+        let mut safe = (0, 0);
+        while let Some(line) = lines.next_line().await? {
+            cx.timings.mark(Phase::Read);
+            // Parsing and checking a report both happen in the fold, for both parts at once
+            safe = fold_safe_reports(safe, line, cx.parts)?;
+            cx.timings.mark(Phase::Solve);
+        }
+        cx.timings.mark(Phase::Read);
+        let (p1_safe, p2_safe) = safe;
+
+        Ok(cx.parts.answer(p1_safe, p2_safe))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Day02, EXAMPLE};
+    use crate::{answer::Value, problems::solve_example};
+
+    #[test]
+    fn example() {
+        let answer = solve_example::<Day02>(EXAMPLE);
+        assert_eq!(answer.part1, Some(Value::UInt(2)));
+        assert_eq!(answer.part2, Some(Value::UInt(4)));
+    }
+}
@@ -0,0 +1,206 @@
+use picoserve::io::Read;
+
+use crate::{
+    answer::Answer,
+    error::IntoAer,
+    problems::{Context, Example, Solver},
+    timing::Phase,
+};
+
+/// Where the scanner has got to in the instruction it's currently looking at.
+///
+/// None of the instructions can start part way through another one, so whenever a byte doesn't fit
+/// the current state we can go back to `Idle` and look at that byte again from there.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum State {
+    Idle,
+    M,
+    Mu,
+    Mul,
+    /// Inside `mul(`, reading the first operand
+    X { x: u32, digits: u8 },
+    /// Past the comma, reading the second operand
+    Y { x: u32, y: u32, digits: u8 },
+    D,
+    Do,
+    /// `do(`
+    DoOpen,
+    Don,
+    DonQuote,
+    DonQuoteT,
+    /// `don't(`
+    DontOpen,
+}
+
+/// Operands are one to three digits long
+const MAX_DIGITS: u8 = 3;
+
+/// Byte at a time scanner for the corrupted memory dump. Doesn't need to see the whole input at
+/// once, it can be fed however much of the input happens to be available.
+pub struct Scanner {
+    state: State,
+    /// Whether `mul` instructions are currently enabled, for part 2
+    enabled: bool,
+    part1: u64,
+    part2: u64,
+}
+
+impl Default for Scanner {
+    fn default() -> Self {
+        Self {
+            state: State::Idle,
+            enabled: true,
+            part1: 0,
+            part2: 0,
+        }
+    }
+}
+
+impl Scanner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the next chunk of the input through the scanner.
+    pub fn feed(&mut self, input: &[u8]) {
+        for b in input {
+            self.state = match self.step(*b) {
+                Some(state) => state,
+                None => Self::start(*b),
+            };
+        }
+    }
+
+    /// Sum of every `mul`, and the sum of just the enabled ones, for everything fed in so far.
+    pub fn answers(&self) -> (u64, u64) {
+        (self.part1, self.part2)
+    }
+
+    /// What a byte does when we aren't part way through an instruction.
+    fn start(b: u8) -> State {
+        match b {
+            b'm' => State::M,
+            b'd' => State::D,
+            _ => State::Idle,
+        }
+    }
+
+    /// Advance the current instruction by one byte. `None` if the byte doesn't belong there.
+    fn step(&mut self, b: u8) -> Option<State> {
+        let state = match (self.state, b) {
+            (State::M, b'u') => State::Mu,
+            (State::Mu, b'l') => State::Mul,
+            (State::Mul, b'(') => State::X { x: 0, digits: 0 },
+            (State::X { x, digits }, b'0'..=b'9') if digits < MAX_DIGITS => State::X {
+                x: x * 10 + u32::from(b - b'0'),
+                digits: digits + 1,
+            },
+            (State::X { x, digits }, b',') if digits > 0 => State::Y { x, y: 0, digits: 0 },
+            (State::Y { x, y, digits }, b'0'..=b'9') if digits < MAX_DIGITS => State::Y {
+                x,
+                y: y * 10 + u32::from(b - b'0'),
+                digits: digits + 1,
+            },
+            (State::Y { x, y, digits }, b')') if digits > 0 => {
+                let product = u64::from(x * y);
+                self.part1 += product;
+                if self.enabled {
+                    self.part2 += product;
+                }
+                State::Idle
+            },
+            (State::D, b'o') => State::Do,
+            (State::Do, b'(') => State::DoOpen,
+            (State::DoOpen, b')') => {
+                self.enabled = true;
+                State::Idle
+            },
+            (State::Do, b'n') => State::Don,
+            (State::Don, b'\'') => State::DonQuote,
+            (State::DonQuote, b't') => State::DonQuoteT,
+            (State::DonQuoteT, b'(') => State::DontOpen,
+            (State::DontOpen, b')') => {
+                self.enabled = false;
+                State::Idle
+            },
+            _ => return None,
+        };
+        Some(state)
+    }
+}
+
+const EXAMPLE_1: &str = "xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))";
+const EXAMPLE_2: &str = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+
+/// day 3: Corrupted multiplication instructions
+pub struct Day03;
+
+impl Solver for Day03 {
+    const DAY: u32 = 3;
+    const TITLE: &'static str = "Mull It Over";
+    // Each part has its own example
+    const EXAMPLES: &'static [Example] = &[
+        Example {
+            input: EXAMPLE_1,
+            part1: Some("161"),
+            part2: None,
+        },
+        Example {
+            input: EXAMPLE_2,
+            part1: None,
+            part2: Some("48"),
+        },
+    ];
+
+    async fn solve<R: Read>(r: &mut R, _input_len: usize, cx: &mut Context) -> crate::Result<Answer> {
+        let mut scanner = Scanner::new();
+        let mut buf = [0u8; 256];
+        loop {
+            let read_size = r.read(&mut buf).await.into_aer()?;
+            cx.timings.mark(Phase::Read);
+            if read_size == 0 {
+                break;
+            }
+            scanner.feed(&buf[..read_size]);
+            cx.timings.mark(Phase::Solve);
+        }
+        // The scanner works out both as it goes, there's nothing to save by skipping one
+        let (part1, part2) = scanner.answers();
+        Ok(cx.parts.answer(part1, part2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Scanner, EXAMPLE_1, EXAMPLE_2};
+
+    #[test]
+    fn part1_example() {
+        let mut scanner = Scanner::new();
+        scanner.feed(EXAMPLE_1.as_bytes());
+        assert_eq!(scanner.answers().0, 161);
+    }
+
+    #[test]
+    fn part2_example() {
+        let mut scanner = Scanner::new();
+        scanner.feed(EXAMPLE_2.as_bytes());
+        assert_eq!(scanner.answers(), (161, 48));
+    }
+
+    #[test]
+    fn split_across_chunks() {
+        let mut scanner = Scanner::new();
+        for b in EXAMPLE_2.as_bytes() {
+            scanner.feed(core::slice::from_ref(b));
+        }
+        assert_eq!(scanner.answers(), (161, 48));
+    }
+
+    #[test]
+    fn rejects_long_operands() {
+        let mut scanner = Scanner::new();
+        scanner.feed(b"mul(1234,5)mul(12,3456)mul(,2)mul(2,)mmul(3,3)");
+        assert_eq!(scanner.answers().0, 9);
+    }
+}
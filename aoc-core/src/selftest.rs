@@ -0,0 +1,132 @@
+//! Checking every solver against the examples from its puzzle, so a regression shows up as soon as
+//! the board boots rather than as a wrong answer some time later.
+//!
+//! The firmware runs it once at start up. The results are kept here for the index and self-test
+//! pages, one bit per day.
+
+use alloc::string::ToString;
+
+use portable_atomic::{AtomicU32, Ordering};
+
+use crate::{
+    answer::{Check, Checks},
+    problems::{self, Context, Day, Status},
+};
+
+/// Days that have been through the self-test.
+static TESTED: AtomicU32 = AtomicU32::new(0);
+/// Days that failed it.
+static BROKEN: AtomicU32 = AtomicU32::new(0);
+
+/// How a day did in the self-test.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Health {
+    /// The self-test hasn't got to it, or it's a stub.
+    Untested,
+    Passed,
+    /// At least one example gave the wrong answer, or an error.
+    Broken,
+}
+
+fn bit(day: u32) -> u32 {
+    1 << day
+}
+
+/// How `day` did in the last self-test.
+pub fn health(day: u32) -> Health {
+    if TESTED.load(Ordering::Relaxed) & bit(day) == 0 {
+        Health::Untested
+    } else if BROKEN.load(Ordering::Relaxed) & bit(day) != 0 {
+        Health::Broken
+    } else {
+        Health::Passed
+    }
+}
+
+/// Whether every day that has been tested passed.
+pub fn all_passed() -> bool {
+    BROKEN.load(Ordering::Relaxed) == 0
+}
+
+/// Run every implemented day over its examples and record how they did. Yields between examples
+/// so the rest of the board keeps going. Returns how many days are broken.
+pub async fn run() -> u32 {
+    info!("Self-test starting");
+    let mut broken = 0;
+    for entry in problems::DAYS.iter().filter(|entry| entry.status == Status::Implemented) {
+        let passed = check_day(entry).await;
+        TESTED.fetch_or(bit(entry.day), Ordering::Relaxed);
+        if passed {
+            BROKEN.fetch_and(!bit(entry.day), Ordering::Relaxed);
+        } else {
+            BROKEN.fetch_or(bit(entry.day), Ordering::Relaxed);
+            broken += 1;
+        }
+    }
+    if broken == 0 {
+        info!("Self-test passed");
+    } else {
+        error!("Self-test failed, {} days broken", broken);
+    }
+    broken
+}
+
+/// Solve each of the day's examples, logging any that don't come out right.
+async fn check_day(entry: &Day) -> bool {
+    let mut passed = true;
+    for (n, example) in entry.examples.iter().enumerate() {
+        let mut cx = Context::new().with_parts(example.parts());
+        let mut input = example.input.as_bytes();
+        match problems::solve(entry.day, &mut input, example.input.len(), &mut cx).await {
+            Ok(answer) => {
+                let checks = Checks::new(&answer, example.part1, example.part2);
+                for (part, check) in [(1, &checks.part1), (2, &checks.part2)] {
+                    if let Some(Check { expected, pass: false }) = check {
+                        let actual = match part {
+                            1 => answer.part1.as_ref(),
+                            _ => answer.part2.as_ref(),
+                        };
+                        let actual = actual.map(ToString::to_string).unwrap_or_default();
+                        error!(
+                            "Day {} example {} part {}: expected {}, got {}",
+                            entry.day,
+                            n + 1,
+                            part,
+                            expected.as_str(),
+                            actual.as_str()
+                        );
+                    }
+                }
+                passed &= checks.passed();
+            },
+            Err(e) => {
+                error!("Day {} example {} failed: {:?}", entry.day, n + 1, e);
+                passed = false;
+            },
+        }
+        embassy_futures::yield_now().await;
+    }
+    if passed {
+        info!("Day {} passed", entry.day);
+    }
+    passed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_day_passes() {
+        assert_eq!(embassy_futures::block_on(run()), 0);
+        for entry in problems::DAYS {
+            let expected = match entry.status {
+                Status::Implemented => Health::Passed,
+                Status::Stub => Health::Untested,
+            };
+            assert_eq!(health(entry.day), expected, "day {}", entry.day);
+        }
+        assert_eq!(health(25), Health::Untested);
+        assert!(all_passed());
+    }
+}
@@ -0,0 +1,160 @@
+//! Measuring how deep into the stack a solve goes.
+//!
+//! Before a solve, the unused part of the stack is painted with a known pattern. Afterwards, the
+//! lowest word that no longer holds the pattern is as far down as anything reached. Recursive
+//! solvers can run out of stack well before they run out of heap, and the board just resets when
+//! that happens, so this is the only warning we get.
+
+use core::ptr;
+
+use portable_atomic::{AtomicPtr, Ordering};
+use serde::Serialize;
+
+/// What unused stack gets filled with. Anything that looks unlike an address or a small number.
+pub const PAINT: u32 = 0xC0FF_EE55;
+
+/// Space left untouched just below the stack pointer, for the frames doing the painting.
+const PAINT_MARGIN: usize = 256;
+
+/// Where to find the stack. Only the firmware installs one; the host tools run on a normal thread
+/// stack which isn't ours to scribble over, so they go without stack figures.
+pub struct StackProbe {
+    /// Lowest address the stack can grow down to, or that's safe to paint if that's higher, e.g.
+    /// above anything the platform keeps at the bottom of the stack.
+    pub limit: fn() -> usize,
+    /// The current stack pointer.
+    pub pointer: fn() -> usize,
+}
+
+static PROBE: AtomicPtr<StackProbe> = AtomicPtr::new(ptr::null_mut());
+
+/// Install the stack probe. Call it once at start up, before serving anything.
+pub fn set_stack_probe(probe: &'static StackProbe) {
+    PROBE.store(ptr::from_ref(probe).cast_mut(), Ordering::Release);
+}
+
+fn probe() -> Option<&'static StackProbe> {
+    let probe = PROBE.load(Ordering::Acquire);
+    // SAFETY: the only non-null pointers ever stored come from `&'static StackProbe`s
+    unsafe { probe.as_ref() }
+}
+
+/// A stretch of memory filled with [`PAINT`], from `bottom` up to (not including) `top`.
+pub struct PaintedRegion {
+    bottom: usize,
+    top: usize,
+}
+
+impl PaintedRegion {
+    /// Fill `bottom..top` with [`PAINT`]. Both ends are rounded inwards to whole words.
+    ///
+    /// # Safety
+    ///
+    /// `bottom..top` must be valid for writes and must not hold anything live, e.g. the part of
+    /// the stack below the stack pointer.
+    pub unsafe fn paint(bottom: usize, top: usize) -> Self {
+        let bottom = bottom.next_multiple_of(size_of::<u32>());
+        let top = (top - top % size_of::<u32>()).max(bottom);
+        for word in (bottom..top).step_by(size_of::<u32>()) {
+            // SAFETY: in bounds and aligned, and the caller promises nothing else is using it
+            unsafe { (word as *mut u32).write_volatile(PAINT) };
+        }
+        Self { bottom, top }
+    }
+
+    /// Lowest address that no longer holds the paint, or the top of the region if nothing has
+    /// reached into it yet.
+    pub fn low_water(&self) -> usize {
+        (self.bottom..self.top)
+            .step_by(size_of::<u32>())
+            // SAFETY: `paint` was promised this memory was there. It may since have been used by
+            // someone else, but any bit pattern is a valid `u32`.
+            .find(|&word| unsafe { (word as *const u32).read_volatile() } != PAINT)
+            .unwrap_or(self.top)
+    }
+}
+
+/// Stack figures for one solve, in bytes.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct StackUsage {
+    /// How far below the start of the solve the stack reached.
+    pub used: usize,
+    /// How much was left below that, at the deepest point.
+    pub free: usize,
+}
+
+/// Watches the stack over a solve, if there's a [`StackProbe`].
+///
+/// Everything sharing the stack in the meantime counts as well: other tasks run on it whenever
+/// the solve is waiting on the network, and so do interrupts. The figure is an upper bound.
+pub struct StackWatch {
+    start: usize,
+    limit: usize,
+    region: Option<PaintedRegion>,
+}
+
+impl StackWatch {
+    pub fn start() -> Self {
+        let Some(probe) = probe() else {
+            return Self { start: 0, limit: 0, region: None };
+        };
+        let start = (probe.pointer)();
+        let limit = (probe.limit)();
+        // SAFETY: everything between the bottom of the stack and the stack pointer is unused,
+        // less the margin left for this function's own frame
+        let region = unsafe { PaintedRegion::paint(limit, start.saturating_sub(PAINT_MARGIN)) };
+        Self { start, limit, region: Some(region) }
+    }
+
+    pub fn finish(self) -> Option<StackUsage> {
+        let low_water = self.region?.low_water();
+        Some(StackUsage {
+            used: self.start.saturating_sub(low_water),
+            free: low_water.saturating_sub(self.limit),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Use a word of the pretend stack. Volatile, as nothing visibly reads it back.
+    fn touch(word: &mut u32, value: u32) {
+        // SAFETY: it's a valid reference
+        unsafe { ptr::write_volatile(word, value) };
+    }
+
+    #[test]
+    fn low_water_mark() {
+        let mut stack = [0u32; 64];
+        let bottom = stack.as_mut_ptr() as usize;
+        let top = bottom + size_of_val(&stack);
+
+        // SAFETY: the array isn't used for anything else while it's painted
+        let region = unsafe { PaintedRegion::paint(bottom, top) };
+        assert_eq!(region.low_water(), top);
+        assert!(stack.iter().all(|&word| word == PAINT));
+
+        // The stack grows down, so a call 10 words deep dirties the top 10 words
+        for word in &mut stack[54..] {
+            touch(word, 7);
+        }
+        // A value that happens to match the paint only hides that one word
+        touch(&mut stack[60], PAINT);
+        assert_eq!(region.low_water(), bottom + 54 * size_of::<u32>());
+
+        touch(&mut stack[20], 0);
+        assert_eq!(region.low_water(), bottom + 20 * size_of::<u32>());
+    }
+
+    #[test]
+    fn unaligned_bounds() {
+        let mut stack = [0u32; 8];
+        let bottom = stack.as_mut_ptr() as usize;
+        // SAFETY: as above, and the rounded bounds stay inside the array
+        let region = unsafe { PaintedRegion::paint(bottom + 1, bottom + 4 * size_of::<u32>() + 3) };
+        assert_eq!(stack, [0, PAINT, PAINT, PAINT, 0, 0, 0, 0]);
+        assert_eq!(region.low_water(), bottom + 4 * size_of::<u32>());
+    }
+}
@@ -0,0 +1,557 @@
+//! Keeping each day's last input, and the answers it got, in flash so they survive a reboot.
+//!
+//! Everything goes in an append-only log, see [`log`], on whatever [`Flash`] the binary installs.
+//! The firmware installs a partition of the ESP32's own flash, the host tools one in memory or in
+//! a file. Without one, nothing is saved.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{cell::RefCell, future::Future, ops::Range};
+
+use critical_section::Mutex;
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, signal::Signal};
+use picoserve::io::{ErrorKind, ErrorType, Read};
+
+use crate::{
+    answer::{Answer, Value},
+    error::AerError,
+    problems::{Parts, CALENDAR},
+    Result,
+};
+
+mod log;
+use log::{Log, Record, WORD};
+
+/// What went wrong talking to the flash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlashError {
+    /// Past the end, or not lined up with whole words.
+    OutOfBounds,
+    /// Too big to go in the log.
+    TooBig,
+    /// The flash itself didn't do as it was told.
+    Device,
+}
+
+/// Some NOR flash: it reads like memory, erases a sector at a time to all `0xFF`s, and writes can
+/// only clear bits. Writes are whole words at word aligned offsets. Everything blocks until it's
+/// done, which is tens of milliseconds for an erase on the board.
+pub trait Flash {
+    /// Bytes in an erase sector.
+    fn sector_size(&self) -> usize;
+    fn sectors(&self) -> usize;
+    fn read(&mut self, offset: usize, buf: &mut [u8]) -> core::result::Result<(), FlashError>;
+    fn write(&mut self, offset: usize, data: &[u8]) -> core::result::Result<(), FlashError>;
+    fn erase(&mut self, sector: usize) -> core::result::Result<(), FlashError>;
+}
+
+impl<F: Flash + ?Sized> Flash for &mut F {
+    fn sector_size(&self) -> usize {
+        (**self).sector_size()
+    }
+
+    fn sectors(&self) -> usize {
+        (**self).sectors()
+    }
+
+    fn read(&mut self, offset: usize, buf: &mut [u8]) -> core::result::Result<(), FlashError> {
+        (**self).read(offset, buf)
+    }
+
+    fn write(&mut self, offset: usize, data: &[u8]) -> core::result::Result<(), FlashError> {
+        (**self).write(offset, data)
+    }
+
+    fn erase(&mut self, sector: usize) -> core::result::Result<(), FlashError> {
+        (**self).erase(sector)
+    }
+}
+
+/// Flash simulated in memory, with the same rules as the real thing.
+pub struct MemFlash {
+    sector_size: usize,
+    data: Vec<u8>,
+    /// Bytes that can still be written before the simulated power cut.
+    budget: usize,
+}
+
+impl MemFlash {
+    /// Freshly erased flash.
+    pub fn new(sector_size: usize, sectors: usize) -> Self {
+        Self::from_bytes(sector_size, alloc::vec![0xFF; sector_size * sectors])
+    }
+
+    /// Flash holding `data`, e.g. read back from a file. Any partial sector at the end is dropped.
+    pub fn from_bytes(sector_size: usize, mut data: Vec<u8>) -> Self {
+        data.truncate(data.len() - data.len() % sector_size);
+        Self { sector_size, data, budget: usize::MAX }
+    }
+
+    /// Everything on the flash.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Cut the power after another `bytes` bytes have been written: the write that goes past it
+    /// only gets part way, and it and everything after fail.
+    pub fn fail_after(&mut self, bytes: usize) {
+        self.budget = bytes;
+    }
+
+    fn range(&self, offset: usize, len: usize) -> core::result::Result<Range<usize>, FlashError> {
+        let end = offset.checked_add(len).ok_or(FlashError::OutOfBounds)?;
+        if end > self.data.len() {
+            return Err(FlashError::OutOfBounds);
+        }
+        Ok(offset..end)
+    }
+}
+
+impl Flash for MemFlash {
+    fn sector_size(&self) -> usize {
+        self.sector_size
+    }
+
+    fn sectors(&self) -> usize {
+        self.data.len() / self.sector_size
+    }
+
+    fn read(&mut self, offset: usize, buf: &mut [u8]) -> core::result::Result<(), FlashError> {
+        let range = self.range(offset, buf.len())?;
+        buf.copy_from_slice(&self.data[range]);
+        Ok(())
+    }
+
+    fn write(&mut self, offset: usize, data: &[u8]) -> core::result::Result<(), FlashError> {
+        if !offset.is_multiple_of(WORD) || !data.len().is_multiple_of(WORD) {
+            return Err(FlashError::OutOfBounds);
+        }
+        let range = self.range(offset, data.len())?;
+        let written = data.len().min(self.budget);
+        self.budget -= written;
+        for (cell, byte) in self.data[range].iter_mut().zip(&data[..written]) {
+            *cell &= byte;
+        }
+        if written < data.len() {
+            return Err(FlashError::Device);
+        }
+        Ok(())
+    }
+
+    fn erase(&mut self, sector: usize) -> core::result::Result<(), FlashError> {
+        let range = self.range(sector * self.sector_size, self.sector_size)?;
+        if self.budget == 0 {
+            return Err(FlashError::Device);
+        }
+        self.data[range].fill(0xFF);
+        Ok(())
+    }
+}
+
+/// The only kind of record so far. Tagged with the day.
+const KIND_SAVED: u8 = 1;
+
+/// Ahead of the answers and input in a saved record.
+/// `| hash: u64 | parts: u8 | part 1 length: u16 | part 2 length: u16 |`, lengths [`NO_ANSWER`] for
+/// parts without one.
+const SAVED_PREFIX: usize = 13;
+const NO_ANSWER: u16 = u16::MAX;
+
+/// Where a day's saved input is in the log.
+#[derive(Clone)]
+struct Entry {
+    record: Record,
+    /// [`crate::cache::Fnv1a`] of the input, so saving the same input again can be skipped.
+    hash: u64,
+    parts: Parts,
+    part1: Option<Range<usize>>,
+    part2: Option<Range<usize>>,
+    input: Range<usize>,
+}
+
+impl Entry {
+    fn read(log: &mut Installed, record: Record) -> core::result::Result<Option<Self>, FlashError> {
+        if record.len < SAVED_PREFIX {
+            return Ok(None);
+        }
+        let mut prefix = [0; SAVED_PREFIX];
+        log.read(record.payload, &mut prefix)?;
+        let hash = u64::from_le_bytes(prefix[..8].try_into().unwrap_or_default());
+        let mut at = record.payload + SAVED_PREFIX;
+        let mut answer = |len: u16| {
+            (len != NO_ANSWER).then(|| {
+                let range = at..at + usize::from(len);
+                at = range.end;
+                range
+            })
+        };
+        let part1 = answer(u16::from_le_bytes([prefix[9], prefix[10]]));
+        let part2 = answer(u16::from_le_bytes([prefix[11], prefix[12]]));
+        let input = at..record.payload + record.len;
+        if input.start > input.end {
+            return Ok(None);
+        }
+        Ok(Some(Self { record, hash, parts: parts_from_byte(prefix[8]), part1, part2, input }))
+    }
+
+    fn span(&self) -> Range<usize> {
+        self.record.payload..self.input.end
+    }
+}
+
+fn parts_byte(parts: Parts) -> u8 {
+    match parts {
+        Parts::One => 1,
+        Parts::Two => 2,
+        Parts::Both => 3,
+    }
+}
+
+fn parts_from_byte(byte: u8) -> Parts {
+    match byte {
+        1 => Parts::One,
+        2 => Parts::Two,
+        _ => Parts::Both,
+    }
+}
+
+type Installed = Log<&'static mut (dyn Flash + Send)>;
+
+struct Storage {
+    log: Installed,
+    /// By day.
+    saved: [Option<Entry>; *CALENDAR.end() as usize + 1],
+}
+
+/// Taken out while it's in use, see [`with_storage`].
+static STORAGE: Mutex<RefCell<Option<Storage>>> = Mutex::new(RefCell::new(None));
+
+/// Run `f` on the storage, if there is any. It's taken out of [`STORAGE`] meanwhile rather than
+/// used under the lock, as a flash erase takes far too long to keep interrupts off for. Flash
+/// access blocks, so nothing else can want it in the meantime.
+fn with_storage<T>(f: impl FnOnce(&mut Storage) -> T) -> Option<T> {
+    let mut storage = critical_section::with(|cs| STORAGE.borrow_ref_mut(cs).take())?;
+    let result = f(&mut storage);
+    critical_section::with(|cs| *STORAGE.borrow_ref_mut(cs) = Some(storage));
+    Some(result)
+}
+
+/// Whether there's storage to save inputs to.
+pub fn installed() -> bool {
+    critical_section::with(|cs| STORAGE.borrow_ref(cs).is_some())
+}
+
+/// Install the flash to keep inputs in, and find what's already saved there. Call it once at start
+/// up, before serving anything. Returns how many days have an input saved.
+pub fn install(flash: &'static mut (dyn Flash + Send)) -> Result<usize> {
+    let mut newest: [Option<Record>; *CALENDAR.end() as usize + 1] = Default::default();
+    let mut log = Log::mount(flash, |record| {
+        let day = usize::from(record.tag);
+        if record.kind != KIND_SAVED || day >= newest.len() {
+            return;
+        }
+        if newest[day].is_none_or(|newest| record.seq > newest.seq) {
+            newest[day] = Some(record);
+        }
+    })
+    .map_err(AerError::Flash)?;
+    let mut saved: [Option<Entry>; *CALENDAR.end() as usize + 1] = Default::default();
+    for (slot, record) in saved.iter_mut().zip(newest) {
+        if let Some(record) = record {
+            *slot = Entry::read(&mut log, record).map_err(AerError::Flash)?;
+        }
+    }
+    let count = saved.iter().flatten().count();
+    info!("Flash storage mounted, {} days have saved inputs", count);
+    critical_section::with(|cs| *STORAGE.borrow_ref_mut(cs) = Some(Storage { log, saved }));
+    Ok(count)
+}
+
+/// Save `input` as `day`'s last input, along with its answers. Does nothing if there's no storage
+/// installed, or if it's the same input and parts as last time, to spare the flash. Returns
+/// whether anything was written.
+pub fn save(day: u32, hash: u64, parts: Parts, answer: &Answer, input: &[u8]) -> Result<bool> {
+    let Some(day) = u8::try_from(day).ok().filter(|day| CALENDAR.contains(&u32::from(*day))) else {
+        return Ok(false);
+    };
+    let text1 = answer.part1.as_ref().map(Value::to_string).unwrap_or_default();
+    let text2 = answer.part2.as_ref().map(Value::to_string).unwrap_or_default();
+    let length = |value: &Option<Value>, text: &String| match value {
+        Some(_) => u16::try_from(text.len()).map_err(|_| AerError::Flash(FlashError::TooBig)),
+        None => Ok(NO_ANSWER),
+    };
+    let mut prefix = [0; SAVED_PREFIX];
+    prefix[..8].copy_from_slice(&hash.to_le_bytes());
+    prefix[8] = parts_byte(parts);
+    prefix[9..11].copy_from_slice(&length(&answer.part1, &text1)?.to_le_bytes());
+    prefix[11..13].copy_from_slice(&length(&answer.part2, &text2)?.to_le_bytes());
+
+    with_storage(|storage| {
+        let slot = usize::from(day);
+        if storage.saved[slot].as_ref().is_some_and(|entry| entry.hash == hash && entry.parts == parts) {
+            return Ok(false);
+        }
+        let pieces = [&prefix[..], text1.as_bytes(), text2.as_bytes(), input];
+        let record = storage.log.append(KIND_SAVED, day, &pieces).map_err(AerError::Flash)?;
+        // Anything the new record took the place of is gone
+        let erased = storage.log.erased_by(&record);
+        for entry in &mut storage.saved {
+            if entry.as_ref().is_some_and(|entry| overlaps(&entry.span(), &erased)) {
+                *entry = None;
+            }
+        }
+        storage.saved[slot] = Entry::read(&mut storage.log, record).map_err(AerError::Flash)?;
+        info!("Saved day {}'s input, {} bytes", day, input.len());
+        Ok(true)
+    })
+    .unwrap_or(Ok(false))
+}
+
+/// Inputs bigger than this aren't saved. Saving an input means keeping a copy of it in memory
+/// while it's solved and until it's written, so this leaves most of the heap to the solver.
+pub const SAVE_LIMIT: usize = 16 * 1024;
+
+/// How many saves can be waiting to be written.
+const PENDING_CAPACITY: usize = 4;
+
+/// A save waiting for [`write_pending`].
+struct Pending {
+    day: u32,
+    hash: u64,
+    parts: Parts,
+    answer: Answer,
+    input: Vec<u8>,
+}
+
+/// Oldest first.
+static PENDING: Mutex<RefCell<heapless::Vec<Pending, PENDING_CAPACITY>>> =
+    Mutex::new(RefCell::new(heapless::Vec::new()));
+/// Wakes [`writer`] when there's something in [`PENDING`].
+static QUEUED: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+/// Queue `input` to be saved as `day`'s last input by [`writer`], so the request that solved it
+/// doesn't wait on the flash. Does nothing if there's no storage installed, or if the input is over
+/// [`SAVE_LIMIT`]. A newer save for the same day replaces one that's still waiting, and if too many
+/// are waiting the oldest is dropped.
+pub fn queue(day: u32, hash: u64, parts: Parts, answer: &Answer, input: Vec<u8>) {
+    if !installed() {
+        return;
+    }
+    if input.len() > SAVE_LIMIT {
+        info!("Day {}'s input is too big to save, {} bytes", day, input.len());
+        return;
+    }
+    let Ok(answer) = answer.try_clone() else {
+        warn!("No room to save day {}'s answer", day);
+        return;
+    };
+    let pending = Pending { day, hash, parts, answer, input };
+    // Dropped outside the lock
+    let replaced = critical_section::with(|cs| {
+        let mut queue = PENDING.borrow_ref_mut(cs);
+        let replaced = match queue.iter().position(|waiting| waiting.day == day) {
+            Some(at) => Some(queue.remove(at)),
+            None if queue.is_full() => Some(queue.remove(0)),
+            None => None,
+        };
+        queue.push(pending).ok();
+        replaced
+    });
+    if let Some(replaced) = replaced.filter(|replaced| replaced.day != day) {
+        warn!("Too many saves waiting, dropped day {}'s", replaced.day);
+    }
+    QUEUED.signal(());
+}
+
+/// Write every save that's waiting. Returns how many were written; ones that fail are logged and
+/// dropped.
+pub fn write_pending() -> usize {
+    let mut written = 0;
+    while let Some(pending) = critical_section::with(|cs| {
+        let mut queue = PENDING.borrow_ref_mut(cs);
+        (!queue.is_empty()).then(|| queue.remove(0))
+    }) {
+        match save(pending.day, pending.hash, pending.parts, &pending.answer, &pending.input) {
+            Ok(true) => written += 1,
+            Ok(false) => {},
+            Err(e) => warn!("Couldn't save day {}'s input: {:?}", pending.day, e),
+        }
+    }
+    written
+}
+
+/// Writes saves as they're queued. The binary runs this as a task of its own: flash access blocks,
+/// and an erase takes tens of milliseconds on the board, which is better spent outside the request
+/// that solved the input. It still holds up whatever else is running at the time.
+pub async fn writer() {
+    loop {
+        QUEUED.wait().await;
+        write_pending();
+    }
+}
+
+fn overlaps(a: &Range<usize>, b: &Range<usize>) -> bool {
+    a.start < b.end && b.start < a.end
+}
+
+/// A day's saved input and answers, as far as the input page needs to know.
+pub struct Saved {
+    pub input_len: usize,
+    pub parts: Parts,
+    pub part1: Option<String>,
+    pub part2: Option<String>,
+}
+
+/// What's saved for `day`, if anything.
+pub fn saved(day: u32) -> Option<Saved> {
+    with_storage(|storage| {
+        let entry = storage.saved.get(usize::try_from(day).ok()?)?.clone()?;
+        let mut answer = |range: Option<Range<usize>>| {
+            let range = range?;
+            let mut text = alloc::vec![0; range.len()];
+            storage.log.read(range.start, &mut text).ok()?;
+            String::from_utf8(text).ok()
+        };
+        Some(Saved {
+            input_len: entry.input.len(),
+            parts: entry.parts,
+            part1: answer(entry.part1),
+            part2: answer(entry.part2),
+        })
+    })
+    .flatten()
+}
+
+/// Reads a day's saved input back out of flash, a bit at a time, for the solver.
+pub struct SavedInput {
+    day: usize,
+    seq: u32,
+    pos: usize,
+    end: usize,
+}
+
+impl SavedInput {
+    /// Start reading `day`'s saved input.
+    pub fn open(day: u32) -> Result<Self> {
+        let found = usize::try_from(day).ok().and_then(|slot| {
+            let entry = with_storage(|storage| storage.saved.get(slot)?.clone()).flatten()?;
+            Some(Self { day: slot, seq: entry.record.seq, pos: entry.input.start, end: entry.input.end })
+        });
+        found.ok_or(AerError::NothingSaved { day })
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.pos
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl ErrorType for SavedInput {
+    type Error = ErrorKind;
+}
+
+impl SavedInput {
+    fn read_now(&mut self, buf: &mut [u8]) -> core::result::Result<usize, ErrorKind> {
+        let n = buf.len().min(self.len());
+        if n == 0 {
+            return Ok(0);
+        }
+        with_storage(|storage| {
+            // Make sure it hasn't been replaced since it was opened
+            if storage.saved[self.day].as_ref().is_none_or(|entry| entry.record.seq != self.seq) {
+                return Err(ErrorKind::NotFound);
+            }
+            storage.log.read(self.pos, &mut buf[..n]).map_err(|_| ErrorKind::Other)
+        })
+        .ok_or(ErrorKind::NotFound)??;
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Flash reads block, so there's never anything to wait for.
+impl Read for SavedInput {
+    fn read(&mut self, buf: &mut [u8]) -> impl Future<Output = core::result::Result<usize, ErrorKind>> {
+        core::future::ready(self.read_now(buf))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::boxed::Box;
+
+    use super::*;
+
+    fn read_all(mut reader: SavedInput) -> Vec<u8> {
+        let mut input = alloc::vec![0; reader.len()];
+        let mut done = 0;
+        while done < input.len() {
+            done += embassy_futures::block_on(reader.read(&mut input[done..])).unwrap();
+        }
+        input
+    }
+
+    // The storage is global, so everything that uses it is in the one test
+    #[test]
+    fn saved_inputs() {
+        assert!(saved(1).is_none());
+        assert!(!save(1, 1, Parts::Both, &Answer::new(), b"x").unwrap());
+
+        let flash = Box::leak(Box::new(MemFlash::new(256, 16)));
+        assert_eq!(install(flash).unwrap(), 0);
+        let answer = Answer::new().with_part1(11u32).with_part2("co,de");
+        assert!(save(1, 7, Parts::Both, &answer, b"3   4\n4   3\n").unwrap());
+        // Same again isn't written twice
+        assert!(!save(1, 7, Parts::Both, &answer, b"3   4\n4   3\n").unwrap());
+        assert!(save(2, 8, Parts::One, &Answer::new().with_part1(2u32), b"1 2 3").unwrap());
+
+        let day1 = saved(1).unwrap();
+        assert_eq!(day1.input_len, 12);
+        assert_eq!((day1.part1.as_deref(), day1.part2.as_deref()), (Some("11"), Some("co,de")));
+        let day2 = saved(2).unwrap();
+        assert_eq!((day2.parts, day2.part2), (Parts::One, None));
+        assert_eq!(read_all(SavedInput::open(1).unwrap()), b"3   4\n4   3\n");
+        assert!(matches!(SavedInput::open(3), Err(AerError::NothingSaved { day: 3 })));
+
+        // A reader for an input that's since been replaced stops
+        let mut stale = SavedInput::open(2).unwrap();
+        assert!(save(2, 9, Parts::Both, &Answer::new(), b"4 5 6").unwrap());
+        assert!(embassy_futures::block_on(stale.read(&mut [0; 4])).is_err());
+
+        // Big inputs push the oldest out of the log
+        let big = [b'1'; 1000];
+        for hash in 10..14 {
+            save(3, hash, Parts::Both, &Answer::new(), &big).unwrap();
+        }
+        assert!(saved(1).is_none() && saved(2).is_none());
+        assert_eq!(read_all(SavedInput::open(3).unwrap()), big);
+        assert!(save(2, 9, Parts::Both, &Answer::new(), b"4 5 6").unwrap());
+
+        // And everything comes back after a reboot
+        let flash = with_storage(|storage| {
+            let mut copy = alloc::vec![0; 256 * 16];
+            storage.log.flash().read(0, &mut copy).unwrap();
+            copy
+        })
+        .unwrap();
+        let rebooted = Box::leak(Box::new(MemFlash::from_bytes(256, flash)));
+        assert_eq!(install(rebooted).unwrap(), 2);
+        assert_eq!(read_all(SavedInput::open(3).unwrap()), big);
+        assert_eq!(read_all(SavedInput::open(2).unwrap()), b"4 5 6");
+
+        // Saves from solves wait to be written, the newest for a day wins, and big inputs aren't
+        // saved at all
+        queue(4, 20, Parts::One, &Answer::new(), b"7 8".to_vec());
+        queue(4, 21, Parts::Both, &Answer::new(), b"7 8 9".to_vec());
+        queue(5, 22, Parts::Both, &Answer::new(), alloc::vec![b'1'; SAVE_LIMIT + 1]);
+        assert!(saved(4).is_none());
+        assert_eq!(write_pending(), 1);
+        assert_eq!(read_all(SavedInput::open(4).unwrap()), b"7 8 9");
+        assert!(saved(5).is_none());
+    }
+}
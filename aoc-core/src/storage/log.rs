@@ -0,0 +1,426 @@
+//! The on-flash format: an append-only log of records that wraps around the partition.
+//!
+//! Each sector starts with a small header, written when the sector is erased, saying where the
+//! first record starting in it is. Records follow on from each other and can run across sectors.
+//! Appending always moves forward, so every sector gets erased as often as every other one. When
+//! the log wraps round, the oldest records are erased to make room.
+//!
+//! Every record carries a CRC of itself. A write cut short by a power loss leaves a record that
+//! fails it, and is ignored; the next append moves on to a freshly erased sector rather than trying
+//! to write over it.
+
+use core::ops::Range;
+
+use super::{Flash, FlashError};
+
+/// Where the log's sectors begin. `first` is the offset into the sector's data of the first record
+/// starting in it, or [`NONE`] if a record runs right through it.
+/// `| magic: u32 | first: u32 | crc: u32 |`
+const SECTOR_HEADER: usize = 12;
+const SECTOR_MAGIC: u32 = 0x4C43_4F41; // "AOCL"
+const NONE: u32 = u32::MAX;
+
+/// `| magic: u16 | kind: u8 | tag: u8 | seq: u32 | len: u32 | crc: u32 |` then `len` bytes of
+/// payload, padded out to a whole word. The CRC covers the rest of the header and the payload.
+pub const RECORD_HEADER: usize = 16;
+const RECORD_MAGIC: u16 = 0xA0C5;
+
+/// Flash is written a word at a time.
+pub const WORD: usize = 4;
+
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 0 { crc >> 1 } else { (crc >> 1) ^ 0xEDB8_8320 };
+            bit += 1;
+        }
+        table[i as usize] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// CRC-32, as used by zip and Ethernet, worked out a piece at a time.
+#[derive(Clone, Copy)]
+pub struct Crc32(u32);
+
+impl Crc32 {
+    pub const fn new() -> Self {
+        Self(u32::MAX)
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = CRC_TABLE[((self.0 ^ u32::from(b)) & 0xFF) as usize] ^ (self.0 >> 8);
+        }
+    }
+
+    pub fn finish(self) -> u32 {
+        !self.0
+    }
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(bytes);
+    crc.finish()
+}
+
+fn padded(len: usize) -> usize {
+    len.next_multiple_of(WORD)
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+/// A record found in the log.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Record {
+    pub kind: u8,
+    pub tag: u8,
+    pub seq: u32,
+    /// Where the payload starts, in the log's own addressing.
+    pub payload: usize,
+    pub len: usize,
+}
+
+/// The log on a [`Flash`]. Positions are in the log's own addressing, which leaves out the sector
+/// headers, so records can be read and written without minding where the sectors end.
+pub struct Log<F> {
+    /// Where the next record goes.
+    head: usize,
+    seq: u32,
+    flash: F,
+}
+
+impl<F: Flash> Log<F> {
+    /// Bytes of records each sector can hold.
+    fn sector_data(&self) -> usize {
+        self.flash.sector_size() - SECTOR_HEADER
+    }
+
+    /// Size of the log, in its own addressing.
+    pub fn capacity(&self) -> usize {
+        self.flash.sectors() * self.sector_data()
+    }
+
+    /// Largest payload a record can have. The newest record has to leave room for at least one
+    /// older one, or saving anything would wipe everything else out.
+    pub fn max_payload(&self) -> usize {
+        self.capacity() / 2 - RECORD_HEADER
+    }
+
+    fn physical(&self, pos: usize) -> usize {
+        let data = self.sector_data();
+        (pos / data) * self.flash.sector_size() + SECTOR_HEADER + pos % data
+    }
+
+    /// Read `buf.len()` bytes of the log from `pos`, across sectors if need be.
+    pub fn read(&mut self, mut pos: usize, mut buf: &mut [u8]) -> Result<(), FlashError> {
+        while !buf.is_empty() {
+            let n = buf.len().min(self.sector_data() - pos % self.sector_data());
+            let (now, rest) = buf.split_at_mut(n);
+            self.flash.read(self.physical(pos), now)?;
+            pos += n;
+            buf = rest;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, mut pos: usize, mut data: &[u8]) -> Result<(), FlashError> {
+        while !data.is_empty() {
+            let n = data.len().min(self.sector_data() - pos % self.sector_data());
+            let (now, rest) = data.split_at(n);
+            self.flash.write(self.physical(pos), now)?;
+            pos += n;
+            data = rest;
+        }
+        Ok(())
+    }
+
+    fn sector_first(&mut self, sector: usize) -> Result<Option<usize>, FlashError> {
+        let mut header = [0; SECTOR_HEADER];
+        self.flash.read(sector * self.flash.sector_size(), &mut header)?;
+        let valid = u32_at(&header, 0) == SECTOR_MAGIC && u32_at(&header, 8) == crc32(&header[..8]);
+        let first = u32_at(&header, 4);
+        Ok((valid && first != NONE).then_some(first as usize))
+    }
+
+    /// Read the record header at `pos` and check it against its payload.
+    fn record_at(&mut self, pos: usize) -> Result<Option<Record>, FlashError> {
+        if pos + RECORD_HEADER > self.capacity() {
+            return Ok(None);
+        }
+        let mut header = [0; RECORD_HEADER];
+        self.read(pos, &mut header)?;
+        let len = u32_at(&header, 8) as usize;
+        let magic = u16::from_le_bytes([header[0], header[1]]);
+        if magic != RECORD_MAGIC || len > self.max_payload() || pos + RECORD_HEADER + len > self.capacity() {
+            return Ok(None);
+        }
+        let payload = pos + RECORD_HEADER;
+        let mut crc = Crc32::new();
+        crc.update(&header[..12]);
+        let mut buf = [0; 64];
+        let mut done = 0;
+        while done < len {
+            let n = (len - done).min(buf.len());
+            self.read(payload + done, &mut buf[..n])?;
+            crc.update(&buf[..n]);
+            done += n;
+        }
+        if crc.finish() != u32_at(&header, 12) {
+            return Ok(None);
+        }
+        Ok(Some(Record { kind: header[2], tag: header[3], seq: u32_at(&header, 4), payload, len }))
+    }
+
+    /// Find every intact record in the log, handing each to `found`, and work out where to carry
+    /// on appending.
+    pub fn mount(flash: F, mut found: impl FnMut(Record)) -> Result<Self, FlashError> {
+        let mut log = Self { head: 0, seq: 0, flash };
+        let mut newest: Option<Record> = None;
+        for sector in 0..log.flash.sectors() {
+            let Some(first) = log.sector_first(sector)? else {
+                continue;
+            };
+            // Only the records that start in this sector, the next sector's header covers the rest
+            let mut pos = sector * log.sector_data() + first;
+            while pos / log.sector_data() == sector {
+                let Some(record) = log.record_at(pos)? else {
+                    break;
+                };
+                found(record);
+                if newest.is_none_or(|newest| record.seq > newest.seq) {
+                    newest = Some(record);
+                }
+                pos = record.payload + padded(record.len);
+            }
+        }
+        if let Some(newest) = newest {
+            log.seq = newest.seq.wrapping_add(1);
+            log.head = newest.payload + padded(newest.len);
+        }
+        // Anything written after the newest record is what's left of a write that was cut short.
+        // It can't be written over, so skip to the next sector.
+        if !log.clean_from(log.head)? {
+            warn!("Flash log has a partly written record at {}, skipping past it", log.head);
+            log.head = log.head.next_multiple_of(log.sector_data());
+        }
+        Ok(log)
+    }
+
+    /// Whether the rest of the sector from `pos` is still erased.
+    fn clean_from(&mut self, mut pos: usize) -> Result<bool, FlashError> {
+        if pos.is_multiple_of(self.sector_data()) {
+            // Gets erased before it's used
+            return Ok(true);
+        }
+        let end = pos.next_multiple_of(self.sector_data());
+        let mut buf = [0; 64];
+        while pos < end {
+            let n = (end - pos).min(buf.len());
+            self.read(pos, &mut buf[..n])?;
+            if buf[..n].iter().any(|&b| b != 0xFF) {
+                return Ok(false);
+            }
+            pos += n;
+        }
+        Ok(true)
+    }
+
+    /// Append a record made of `pieces`, one after the other. Returns where it went.
+    pub fn append(&mut self, kind: u8, tag: u8, pieces: &[&[u8]]) -> Result<Record, FlashError> {
+        let len: usize = pieces.iter().map(|piece| piece.len()).sum();
+        if len > self.max_payload() {
+            return Err(FlashError::TooBig);
+        }
+        let total = RECORD_HEADER + padded(len);
+        let mut pos = self.head;
+        if pos + total > self.capacity() {
+            pos = 0;
+        }
+        let end = pos + total;
+
+        // Erase every sector the record runs into, bar the one it starts in if that's already
+        // in use
+        let data = self.sector_data();
+        for sector in pos / data..=(end - 1) / data {
+            let start = sector * data;
+            if start < pos {
+                continue;
+            }
+            let first = match start {
+                _ if start == pos => 0,
+                _ if end < start + data => u32::try_from(end - start).map_err(|_| FlashError::TooBig)?,
+                _ => NONE,
+            };
+            self.flash.erase(sector)?;
+            let mut header = [0; SECTOR_HEADER];
+            header[..4].copy_from_slice(&SECTOR_MAGIC.to_le_bytes());
+            header[4..8].copy_from_slice(&first.to_le_bytes());
+            let crc = crc32(&header[..8]);
+            header[8..].copy_from_slice(&crc.to_le_bytes());
+            self.flash.write(sector * self.flash.sector_size(), &header)?;
+        }
+
+        let mut header = [0; RECORD_HEADER];
+        header[..2].copy_from_slice(&RECORD_MAGIC.to_le_bytes());
+        header[2] = kind;
+        header[3] = tag;
+        header[4..8].copy_from_slice(&self.seq.to_le_bytes());
+        let len32 = u32::try_from(len).map_err(|_| FlashError::TooBig)?;
+        header[8..12].copy_from_slice(&len32.to_le_bytes());
+        let mut crc = Crc32::new();
+        crc.update(&header[..12]);
+        for piece in pieces {
+            crc.update(piece);
+        }
+        header[12..].copy_from_slice(&crc.finish().to_le_bytes());
+        self.write(pos, &header)?;
+
+        // The pieces don't line up with words, so they go through a buffer
+        let mut buf = [0xFF; 64];
+        let mut fill = 0;
+        let mut at = pos + RECORD_HEADER;
+        for mut piece in pieces.iter().copied() {
+            while !piece.is_empty() {
+                let n = piece.len().min(buf.len() - fill);
+                buf[fill..fill + n].copy_from_slice(&piece[..n]);
+                fill += n;
+                piece = &piece[n..];
+                if fill == buf.len() {
+                    self.write(at, &buf)?;
+                    at += fill;
+                    fill = 0;
+                }
+            }
+        }
+        if fill > 0 {
+            let padded = padded(fill);
+            buf[fill..padded].fill(0xFF);
+            self.write(at, &buf[..padded])?;
+        }
+
+        let record = Record { kind, tag, seq: self.seq, payload: pos + RECORD_HEADER, len };
+        self.head = end;
+        self.seq = self.seq.wrapping_add(1);
+        Ok(record)
+    }
+
+    /// The part of the log that was erased to make room for `record`, which had better be the
+    /// last one appended. Anything else that was in there is gone.
+    pub fn erased_by(&self, record: &Record) -> Range<usize> {
+        let start = record.payload - RECORD_HEADER;
+        let end = record.payload + padded(record.len);
+        start.next_multiple_of(self.sector_data())..end.next_multiple_of(self.sector_data())
+    }
+
+    #[cfg(test)]
+    pub fn into_flash(self) -> F {
+        self.flash
+    }
+
+    #[cfg(test)]
+    pub fn flash(&mut self) -> &mut F {
+        &mut self.flash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::storage::MemFlash;
+
+    const SECTOR: usize = 256;
+
+    fn remount(flash: MemFlash) -> (Log<MemFlash>, Vec<Record>) {
+        let mut found = Vec::new();
+        let log = Log::mount(flash, |record| found.push(record)).unwrap();
+        found.sort_by_key(|record| record.seq);
+        (log, found)
+    }
+
+    fn payload(log: &mut Log<MemFlash>, record: &Record) -> Vec<u8> {
+        let mut buf = alloc::vec![0; record.len];
+        log.read(record.payload, &mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn crc() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn records_survive_remounting() {
+        let (mut log, found) = remount(MemFlash::new(SECTOR, 8));
+        assert_eq!(found.len(), 0);
+        log.append(1, 3, &[b"abc", b"defgh"]).unwrap();
+        // Runs across three sectors
+        let across: Vec<u8> = (0..=255).cycle().take(600).collect();
+        log.append(1, 4, &[&across[..7], &across[7..]]).unwrap();
+        log.append(2, 3, &[b""]).unwrap();
+
+        let (mut log, found) = remount(log.into_flash());
+        assert_eq!(found.len(), 3);
+        assert_eq!((found[0].kind, found[0].tag, found[0].seq), (1, 3, 0));
+        assert_eq!(payload(&mut log, &found[0]), b"abcdefgh");
+        assert_eq!(payload(&mut log, &found[1]), across);
+        assert_eq!((found[2].kind, found[2].len), (2, 0));
+
+        // Carries on where it left off
+        let record = log.append(1, 5, &[b"x"]).unwrap();
+        assert_eq!(record.seq, 3);
+        let (_, found) = remount(log.into_flash());
+        assert_eq!(found.len(), 4);
+    }
+
+    #[test]
+    fn wraps_around() {
+        let (mut log, _) = remount(MemFlash::new(SECTOR, 4));
+        let chunk = [7; 200];
+        for _ in 0..20 {
+            log.append(1, 1, &[&chunk]).unwrap();
+        }
+        let last = log.append(1, 2, &[b"last"]).unwrap();
+        let (mut log, found) = remount(log.into_flash());
+        // Only the newest ones are left, and they're all intact
+        assert!(found.len() < 20, "{found:?}");
+        assert_eq!(found.last(), Some(&last));
+        assert!(found.windows(2).all(|pair| pair[0].seq + 1 == pair[1].seq));
+        for record in &found[..found.len() - 1] {
+            assert_eq!(payload(&mut log, record), chunk);
+        }
+        assert!(log.append(1, 1, &[&[0; 1024]]).is_err());
+    }
+
+    #[test]
+    fn power_loss() {
+        for budget in [0, 3, 10, 16, 40, 300] {
+            let (mut log, _) = remount(MemFlash::new(SECTOR, 8));
+            let kept = log.append(1, 1, &[b"kept"]).unwrap();
+            let mut flash = log.into_flash();
+            // The power goes part way through writing the next record
+            flash.fail_after(budget);
+            let (mut log, _) = remount(flash);
+            assert!(log.append(1, 2, &[&[9; 400]]).is_err());
+            let mut flash = log.into_flash();
+            flash.fail_after(usize::MAX);
+
+            let (mut log, found) = remount(flash);
+            assert_eq!(found, [kept], "budget {budget}");
+            let next = log.append(1, 3, &[b"after"]).unwrap();
+            let (mut log, found) = remount(log.into_flash());
+            assert_eq!(found, [kept, next], "budget {budget}");
+            assert_eq!(payload(&mut log, &next), b"after");
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! Where the time goes in a solve.
+//!
+//! Solvers mark the end of each phase as they go, and everything since the previous mark is put
+//! down to that phase. Phases can be marked any number of times and add up, so days that read and
+//! work a line at a time can still split network time from compute time.
+
+use embassy_time::{Duration, Instant};
+
+/// The parts of a solve that get timed separately.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// Waiting on the request body.
+    Read,
+    /// Turning the input into something to work on.
+    Parse,
+    Part1,
+    Part2,
+    /// Working out both parts at once, for days that can't separate them.
+    Solve,
+    /// Anything the solver didn't put down to a phase.
+    Other,
+    /// Building the result page.
+    Render,
+}
+
+impl Phase {
+    /// Every phase, in the order they're shown.
+    pub const ALL: [Phase; 7] = [
+        Phase::Read,
+        Phase::Parse,
+        Phase::Part1,
+        Phase::Part2,
+        Phase::Solve,
+        Phase::Other,
+        Phase::Render,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::Read => "Read input",
+            Phase::Parse => "Parse",
+            Phase::Part1 => "Part 1",
+            Phase::Part2 => "Part 2",
+            Phase::Solve => "Solve",
+            Phase::Other => "Other",
+            Phase::Render => "Render",
+        }
+    }
+}
+
+/// Lap timer for the phases of a solve.
+#[derive(Clone, Debug)]
+pub struct Timings {
+    start: Instant,
+    last: Instant,
+    phases: [Option<Duration>; Phase::ALL.len()],
+}
+
+impl Default for Timings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Timings {
+    /// Start timing from now.
+    pub fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            start: now,
+            last: now,
+            phases: [None; Phase::ALL.len()],
+        }
+    }
+
+    /// End the current stretch of `phase`. The time since the last mark, or since starting, gets
+    /// added to it.
+    pub fn mark(&mut self, phase: Phase) {
+        let now = Instant::now();
+        let lap = now - self.last;
+        self.last = now;
+        let total = &mut self.phases[phase as usize];
+        *total = Some(total.unwrap_or_default() + lap);
+    }
+
+    /// Leave the time since the last mark out of every phase, e.g. time spent measuring something
+    /// else.
+    pub fn skip(&mut self) {
+        self.last = Instant::now();
+    }
+
+    /// Time spent in `phase`, `None` if it was never marked.
+    pub fn get(&self, phase: Phase) -> Option<Duration> {
+        self.phases[phase as usize]
+    }
+
+    /// Every phase that was marked, with its time, in the order of [`Phase::ALL`].
+    pub fn phases(&self) -> impl Iterator<Item = (Phase, Duration)> + '_ {
+        Phase::ALL
+            .into_iter()
+            .filter_map(|phase| Some((phase, self.get(phase)?)))
+    }
+
+    /// Time since starting that hasn't been marked as anything yet.
+    pub fn unmarked(&self) -> Duration {
+        Instant::now() - self.last
+    }
+
+    /// Time since starting.
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// Summary of the times from a benchmark.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Stats {
+    pub runs: usize,
+    pub min: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+    pub max: Duration,
+}
+
+impl Stats {
+    /// Summarise `samples`, sorting them on the way. `None` if there aren't any.
+    pub fn from_samples(samples: &mut [Duration]) -> Option<Self> {
+        samples.sort_unstable();
+        let runs = samples.len();
+        let (&min, &max) = (samples.first()?, samples.last()?);
+        let middle = samples[runs / 2];
+        let median = if runs.is_multiple_of(2) {
+            Duration::from_ticks(u64::midpoint(samples[runs / 2 - 1].as_ticks(), middle.as_ticks()))
+        } else {
+            middle
+        };
+        let total: u64 = samples.iter().map(Duration::as_ticks).sum();
+        Some(Self {
+            runs,
+            min,
+            median,
+            mean: Duration::from_ticks(total / runs as u64),
+            max,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn laps_add_up() {
+        let mut timings = Timings::new();
+        timings.mark(Phase::Read);
+        timings.mark(Phase::Part2);
+        timings.mark(Phase::Read);
+        let marked = timings.phases().fold(Duration::MIN, |total, (_, time)| total + time);
+        assert!(marked + timings.unmarked() <= timings.elapsed());
+
+        let mut phases = timings.phases().map(|(phase, _)| phase);
+        assert_eq!(phases.next(), Some(Phase::Read));
+        assert_eq!(phases.next(), Some(Phase::Part2));
+        assert_eq!(phases.next(), None);
+        assert_eq!(timings.get(Phase::Parse), None);
+    }
+
+    #[test]
+    fn stats() {
+        assert_eq!(Stats::from_samples(&mut []), None);
+
+        let mut samples = [40, 10, 30, 20].map(Duration::from_micros);
+        let stats = Stats::from_samples(&mut samples).unwrap();
+        assert_eq!(stats.runs, 4);
+        assert_eq!(stats.min, Duration::from_micros(10));
+        assert_eq!(stats.median, Duration::from_micros(25));
+        assert_eq!(stats.mean, Duration::from_micros(25));
+        assert_eq!(stats.max, Duration::from_micros(40));
+
+        let mut samples = [5, 1, 90].map(Duration::from_micros);
+        let stats = Stats::from_samples(&mut samples).unwrap();
+        assert_eq!(stats.median, Duration::from_micros(5));
+        assert_eq!(stats.mean, Duration::from_micros(32));
+    }
+}
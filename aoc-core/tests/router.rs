@@ -0,0 +1,620 @@
+//! Feeds raw HTTP requests through the router from `app::make_app`, over an in-memory connection,
+//! and checks what comes back out.
+
+use std::{
+    convert::Infallible,
+    fmt::Write as _,
+    future::{ready, Future},
+    time::Duration,
+};
+
+use aoc_core::{
+    app::{make_app, make_config, HTTP_BUFFER_SIZE},
+    pages::{HTML_FOOTER, MAX_BENCH_RUNS},
+    cache::Fnv1a,
+    problems, selftest,
+    storage::{self, MemFlash},
+};
+use picoserve::{
+    io::{ErrorType, Socket, Write},
+    Timeouts, Timer,
+};
+
+/// One side of a connection: the request bytes are read from a slice, the response goes into a
+/// `Vec`. Reading past the end of the request looks like the client hanging up.
+struct Transport<'a> {
+    request: &'a [u8],
+    response: &'a mut Vec<u8>,
+}
+
+struct Sink<'a>(&'a mut Vec<u8>);
+
+impl ErrorType for Sink<'_> {
+    type Error = Infallible;
+}
+
+impl Write for Sink<'_> {
+    fn write(&mut self, buf: &[u8]) -> impl Future<Output = Result<usize, Infallible>> {
+        self.0.extend_from_slice(buf);
+        ready(Ok(buf.len()))
+    }
+}
+
+impl Socket for Transport<'_> {
+    type Error = Infallible;
+    type ReadHalf<'a> = &'a [u8] where Self: 'a;
+    type WriteHalf<'a> = Sink<'a> where Self: 'a;
+
+    fn split(&mut self) -> (&[u8], Sink<'_>) {
+        (self.request, Sink(self.response))
+    }
+
+    fn shutdown<T: Timer>(
+        self,
+        _timeouts: &Timeouts<T::Duration>,
+        _timer: &mut T,
+    ) -> impl Future<Output = Result<(), picoserve::Error<Infallible>>> {
+        ready(Ok(()))
+    }
+}
+
+/// Nothing here can stall, so nothing ever times out.
+struct NoTimeouts;
+
+impl Timer for NoTimeouts {
+    type Duration = Duration;
+    type TimeoutError = Infallible;
+
+    async fn run_with_timeout<F: Future>(
+        &mut self,
+        _duration: Duration,
+        future: F,
+    ) -> Result<F::Output, Infallible> {
+        Ok(future.await)
+    }
+}
+
+struct Response {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    /// Size of each chunk, for chunked responses.
+    chunks: Vec<usize>,
+}
+
+impl Response {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn text(&self) -> &str {
+        std::str::from_utf8(&self.body).expect("response body should be utf-8")
+    }
+
+    fn parse(raw: &[u8]) -> Self {
+        let split = raw
+            .windows(4)
+            .position(|w| w == b"\r\n\r\n")
+            .expect("response should have a blank line after the headers");
+        let head = std::str::from_utf8(&raw[..split]).unwrap();
+        let mut rest = &raw[split + 4..];
+        let mut lines = head.split("\r\n");
+        let status = lines.next().unwrap().split(' ').nth(1).unwrap().parse().unwrap();
+        let headers = lines
+            .map(|line| {
+                let (name, value) = line.split_once(':').unwrap();
+                (name.to_string(), value.trim().to_string())
+            })
+            .collect();
+        let mut response = Self { status, headers, body: Vec::new(), chunks: Vec::new() };
+
+        if response.header("Transfer-Encoding") == Some("chunked") {
+            loop {
+                let eol = rest.windows(2).position(|w| w == b"\r\n").unwrap();
+                let size =
+                    usize::from_str_radix(std::str::from_utf8(&rest[..eol]).unwrap(), 16).unwrap();
+                rest = &rest[eol + 2..];
+                if size == 0 {
+                    break;
+                }
+                response.body.extend_from_slice(&rest[..size]);
+                response.chunks.push(size);
+                assert_eq!(&rest[size..size + 2], b"\r\n", "chunk should end in CRLF");
+                rest = &rest[size + 2..];
+            }
+        } else {
+            let length: usize = response.header("Content-Length").unwrap().parse().unwrap();
+            response.body = rest[..length].to_vec();
+        }
+        response
+    }
+}
+
+/// Serve a single request, exactly as written, and parse the response.
+fn send(request: &[u8]) -> Response {
+    let app = make_app();
+    let config = make_config(Duration::from_secs);
+    let mut buffer = [0; HTTP_BUFFER_SIZE];
+    let mut raw = Vec::new();
+    let transport = Transport { request, response: &mut raw };
+    let handled = embassy_futures::block_on(picoserve::serve(
+        &app,
+        NoTimeouts,
+        &config,
+        &mut buffer,
+        transport,
+    ))
+    .unwrap();
+    assert_eq!(handled, 1);
+    Response::parse(&raw)
+}
+
+fn get(path: &str) -> Response {
+    send(format!("GET {path} HTTP/1.1\r\nHost: esp32\r\n\r\n").as_bytes())
+}
+
+fn post(path: &str, content_type: Option<&str>, body: &[u8]) -> Response {
+    let mut request = format!("POST {path} HTTP/1.1\r\nHost: esp32\r\n");
+    if let Some(content_type) = content_type {
+        write!(request, "Content-Type: {content_type}\r\n").unwrap();
+    }
+    write!(request, "Content-Length: {}\r\n\r\n", body.len()).unwrap();
+    let mut request = request.into_bytes();
+    request.extend_from_slice(body);
+    send(&request)
+}
+
+const DAY1_EXAMPLE: &str = "3   4\n4   3\n2   5\n1   3\n3   9\n3   3\n";
+
+#[test]
+fn index_is_chunked() {
+    let response = get("/");
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Content-Type"), Some("text/html"));
+    // The page is well over a chunk, make sure it survives being split up
+    assert!(response.chunks.len() > 1, "chunks: {:?}", response.chunks);
+    assert_eq!(response.chunks[0], 1200);
+    let text = response.text();
+    assert!(text.contains(r#"<a href="/day/1">Day 1: Historian Hysteria</a>"#));
+    assert!(text.contains("<li>Day 25 (not implemented)</li>"));
+    assert!(text.ends_with(HTML_FOOTER));
+}
+
+#[test]
+fn self_test_page() {
+    embassy_futures::block_on(selftest::run());
+    let response = get("/selftest");
+    assert_eq!(response.status, 200);
+    let text = response.text();
+    let day3 = r#"Day 3: Mull It Over</a></td><td>2</td><td><span class="pass">passed</span></td></tr>"#;
+    assert!(text.contains(day3), "{text}");
+    assert!(!get("/").text().contains("(broken)"));
+}
+
+#[test]
+fn input_pages() {
+    let response = get("/day/1");
+    assert_eq!(response.status, 200);
+    assert!(response.text().contains("Advent of Code day 1: Historian Hysteria"));
+    assert!(response.text().contains(r#"<textarea name="message""#));
+    assert!(response.text().contains("Inputs can be up to 16384 bytes."));
+    assert!(response.text().contains(r#"<input type="hidden" name="expect1" value="11">"#));
+    assert!(response.text().contains(r#"<input type="submit" value="Run example">"#));
+
+    // Each part of day 3 has its own example, they only check their own part
+    let response = get("/day/3");
+    let text = response.text();
+    assert!(text.contains(r#"value="Run example 2""#));
+    assert!(text.contains(r#"<input type="hidden" name="part" value="2">"#));
+    assert!(text.contains("mul(11,8)undo()?mul(8,5))</textarea>"));
+
+    let response = get("/day/25");
+    assert_eq!(response.status, 501);
+    assert!(response.text().contains("<h2>Day 25 hasn't been implemented yet</h2>"));
+    assert!(!response.text().contains("<form"));
+
+    let response = get("/day/26");
+    assert_eq!(response.status, 404);
+    assert!(response.text().contains("<h2>Day 26 doesn't exist</h2>"));
+}
+
+#[test]
+fn static_files() {
+    let response = get("/static/index.css");
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Content-Type"), Some("text/css"));
+    assert_eq!(response.text(), include_str!("../src/static/index.css"));
+
+    let response = get("/static/icon.png");
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Content-Type"), Some("image/png"));
+    assert_eq!(response.body, include_bytes!("../src/static/icon.png"));
+}
+
+#[test]
+fn unknown_paths() {
+    assert_eq!(get("/nope").status, 404);
+    assert_eq!(get("/day/one").status, 404);
+    assert_eq!(get("/static/missing.css").status, 404);
+}
+
+#[test]
+fn solve_form_posts() {
+    let form = format!("message={DAY1_EXAMPLE}");
+    let response = post("/day/1", Some("text/plain"), form.as_bytes());
+    assert_eq!(response.status, 200);
+    assert!(response.text().contains("Part 1: 11<br>"), "{}", response.text());
+    assert!(response.text().contains("Part 2: 31<br>"));
+    // Day 1 marks every phase but the combined one
+    for phase in ["Read input", "Parse", "Part 1", "Part 2", "Render"] {
+        assert!(response.text().contains(&format!("<tr><td>{phase}</td><td>")), "{phase}");
+    }
+    assert!(!response.text().contains("<tr><td>Solve</td>"));
+
+    let encoded = DAY1_EXAMPLE.replace(' ', "+").replace('\n', "%0A");
+    let response = post(
+        "/day/1",
+        Some("application/x-www-form-urlencoded"),
+        format!("message={encoded}").as_bytes(),
+    );
+    assert!(response.text().contains("Part 1: 11<br>"), "{}", response.text());
+}
+
+#[test]
+fn solve_raw_uploads() {
+    // No `message=` prefix is fine, whatever the content type
+    for content_type in [None, Some("text/plain"), Some("application/octet-stream")] {
+        let response = post("/day/1", content_type, DAY1_EXAMPLE.as_bytes());
+        assert!(response.text().contains("Part 2: 31<br>"), "{content_type:?}");
+    }
+}
+
+#[test]
+fn solve_errors() {
+    let response = post("/day/1", Some("text/plain"), b"message=");
+    assert_eq!(response.status, 400);
+    assert!(response.text().contains("Encountered error: No puzzle input in the request body"));
+
+    let response = post("/day/1", Some("text/plain"), b"");
+    assert_eq!(response.status, 400);
+    assert!(response.text().contains("Encountered error: No puzzle input in the request body"));
+
+    let response = post("/day/1", None, b"1   2\nthree   4\n");
+    assert_eq!(response.status, 400);
+    assert!(response.text().contains("Encountered error: Integer parse error"));
+
+    let long_line = format!("{}   1\n", "1".repeat(100));
+    let response = post("/day/1", None, long_line.as_bytes());
+    assert_eq!(response.status, 413);
+
+    let response = post("/day/25", None, DAY1_EXAMPLE.as_bytes());
+    assert_eq!(response.status, 501);
+    assert!(response.text().contains("<h1>Error 501</h1>"));
+    assert!(response.text().ends_with(HTML_FOOTER));
+
+    let response = post("/day/0", None, DAY1_EXAMPLE.as_bytes());
+    assert_eq!(response.status, 404);
+    assert!(response.text().contains("<h2>Day 0 doesn't exist</h2>"));
+}
+
+#[test]
+fn part_selection() {
+    let response = post("/day/1?part=1", None, DAY1_EXAMPLE.as_bytes());
+    assert!(response.text().contains("Part 1: 11<br>"));
+    assert!(!response.text().contains("Part 2"));
+
+    // As the input form sends it, the form field wins over the query string
+    let form = format!("part=2\r\nmessage={DAY1_EXAMPLE}");
+    let response = post("/day/1?part=1", Some("text/plain"), form.as_bytes());
+    assert!(!response.text().contains("Part 1"), "{}", response.text());
+    assert!(response.text().contains("Part 2: 31<br>"));
+
+    let form = format!("part=both&message={}", DAY1_EXAMPLE.replace('\n', "%0A"));
+    let response = post("/day/1", Some("application/x-www-form-urlencoded"), form.as_bytes());
+    assert!(response.text().contains("Part 1: 11<br>"));
+    assert!(response.text().contains("Part 2: 31<br>"));
+
+    let response = post("/api/day/1?part=2", None, DAY1_EXAMPLE.as_bytes());
+    assert!(response.text().starts_with(r#"{"day":1,"part1":null,"part2":31,"#));
+
+    let response = post("/day/1?part=3", None, DAY1_EXAMPLE.as_bytes());
+    assert_eq!(response.status, 400);
+    let response = post("/api/day/1?part=3", None, DAY1_EXAMPLE.as_bytes());
+    assert_eq!(response.status, 400);
+    assert!(response.text().contains(r#""code":"bad_query""#));
+
+    assert!(get("/day/1").text().contains(r#"<select name="part""#));
+}
+
+#[test]
+fn benchmarks() {
+    let response = post("/day/1?bench=5", None, DAY1_EXAMPLE.as_bytes());
+    assert_eq!(response.status, 200);
+    let text = response.text();
+    assert!(text.contains("Historian Hysteria (benchmark)"));
+    // Only the first run's answers are shown
+    assert_eq!(text.matches("Part 1: 11<br>").count(), 1, "{text}");
+    assert!(text.contains("<tr><td>5</td>"));
+    assert!(text.ends_with(HTML_FOOTER));
+
+    // Unrelated parameters are fine
+    let form = format!("message={DAY1_EXAMPLE}");
+    let response = post("/day/1?x=y&bench=2", Some("text/plain"), form.as_bytes());
+    assert!(response.text().contains("<tr><td>2</td>"), "{}", response.text());
+
+    for query in ["bench=0".to_string(), format!("bench={}", MAX_BENCH_RUNS + 1)] {
+        let response = post(&format!("/day/1?{query}"), None, DAY1_EXAMPLE.as_bytes());
+        assert_eq!(response.status, 400);
+        assert!(response.text().contains("it has to be between 1 and"), "{query}");
+    }
+
+    let response = post("/day/1?bench=lots", None, DAY1_EXAMPLE.as_bytes());
+    assert_eq!(response.status, 400);
+    assert!(response.text().contains("Couldn't make sense of the options"));
+
+    let response = post("/day/1?bench=3", None, b"1   2\nthree   4\n");
+    assert_eq!(response.status, 400);
+    assert!(response.text().contains("Run 1 encountered error: Integer parse error"));
+}
+
+#[test]
+fn expected_answers() {
+    let response = post("/day/1?expect1=11&expect2=31", None, DAY1_EXAMPLE.as_bytes());
+    assert_eq!(response.status, 200);
+    let text = response.text();
+    assert!(text.contains(r#"Part 1: 11 <span class="pass">&#10004; expected 11</span><br>"#), "{text}");
+    assert!(text.contains(r#"Part 2: 31 <span class="pass">"#));
+
+    // As the input form sends it, with part 2 left blank
+    let form = format!("part=both\r\nexpect1=12\r\nexpect2=\r\nmessage={DAY1_EXAMPLE}");
+    let response = post("/day/1", Some("text/plain"), form.as_bytes());
+    assert_eq!(response.status, 422);
+    let text = response.text();
+    assert!(text.contains(r#"Part 1: 11 <span class="fail">&#10008; expected 12</span><br>"#), "{text}");
+    assert!(text.contains("Part 2: 31<br>"));
+
+    // Parts that weren't solved aren't checked
+    let response = post("/day/1?part=1&expect2=30", None, DAY1_EXAMPLE.as_bytes());
+    assert_eq!(response.status, 200);
+
+    let response = post("/day/1?bench=3&expect2=30", None, DAY1_EXAMPLE.as_bytes());
+    assert_eq!(response.status, 422);
+
+    let response = post("/api/day/1?expect1=11&expect2=30", None, DAY1_EXAMPLE.as_bytes());
+    assert_eq!(response.status, 422);
+    let text = response.text();
+    assert!(text.contains(r#""check1":{"expected":"11","pass":true},"#), "{text}");
+    assert!(text.contains(r#""check2":{"expected":"30","pass":false}"#));
+    let response = post("/api/day/1", None, DAY1_EXAMPLE.as_bytes());
+    assert!(!response.text().contains("check1"));
+
+    assert!(get("/day/1").text().contains(r#"<input name="expect1""#));
+}
+
+/// What a browser sends for the day 1 example form.
+#[test]
+fn run_example() {
+    let input = DAY1_EXAMPLE.replace('\n', "\r\n");
+    let form = format!("part=both\r\nexpect1=11\r\nexpect2=31\r\nmessage={input}");
+    let response = post("/day/1", Some("text/plain"), form.as_bytes());
+    assert_eq!(response.status, 200);
+    assert_eq!(response.text().matches(r#"<span class="pass">"#).count(), 2);
+}
+
+/// Expected answers too long to fit ahead of the input are turned away, not solved as input.
+#[test]
+fn long_form_fields() {
+    let expected = "9".repeat(200);
+    let form = format!("part=both\r\nexpect1={expected}\r\nexpect2=31\r\nmessage={DAY1_EXAMPLE}");
+    let response = post("/day/1", Some("text/plain"), form.as_bytes());
+    assert_eq!(response.status, 400);
+    assert!(response.text().contains("Couldn't make sense of the options"), "{}", response.text());
+}
+
+#[test]
+fn answer_cache() {
+    // Nothing else posts this input, other tests running alongside won't get in the way
+    const INPUT: &str = "5   6\n7   8\n1   1\n";
+    let cached = r#""cached":true"#;
+    let response = post("/api/day/1", None, INPUT.as_bytes());
+    assert!(response.text().contains(r#""part1":2,"part2":1,"#), "{}", response.text());
+    assert!(!response.text().contains(cached));
+    // Only looked up when asked
+    let response = post("/api/day/1", None, INPUT.as_bytes());
+    assert!(!response.text().contains(cached));
+    let response = post("/api/day/1?cache=lookup", None, INPUT.as_bytes());
+    assert!(response.text().contains(r#""part1":2,"part2":1,"#));
+    assert!(response.text().contains(cached));
+    // Different parts are filed separately
+    let response = post("/api/day/1?part=1&cache=lookup", None, INPUT.as_bytes());
+    assert!(!response.text().contains(cached));
+
+    let form = format!("part=both\r\ncache=lookup\r\nmessage={INPUT}");
+    let response = post("/day/1", Some("text/plain"), form.as_bytes());
+    assert!(response.text().contains("Answered from the cache"));
+    assert!(response.text().contains("Evaluated in"));
+    let form = format!("part=both\r\ncache=off\r\nmessage={INPUT}");
+    let response = post("/day/1", Some("text/plain"), form.as_bytes());
+    assert!(!response.text().contains("Answered from the cache"));
+
+    // Filed after a normal solve, found by a lookup
+    let other = b"5   6\n7   8\n1   1\n2   9\n";
+    assert!(!post("/api/day/1?part=2", None, other).text().contains(cached));
+    assert!(post("/api/day/1?part=2&cache=lookup", None, other).text().contains(cached));
+
+    let mut hash = Fnv1a::new();
+    hash.update(INPUT.as_bytes());
+    let response = get("/cache");
+    assert_eq!(response.status, 200);
+    assert!(response.text().contains(&format!("<tr><td>1</td><td>both</td><td>{:016x}</td>", hash.finish())));
+
+    let response = post("/cache", None, b"");
+    assert!(response.text().contains("<p>Cleared "), "{}", response.text());
+    let response = post("/api/day/1?cache=lookup", None, INPUT.as_bytes());
+    assert!(!response.text().contains(cached));
+
+    assert_eq!(post("/api/day/1?cache=maybe", None, INPUT.as_bytes()).status, 400);
+}
+
+// Nothing else uses day 2, so the other tests saving their inputs can't get in the way
+#[test]
+fn saved_inputs() {
+    let flash = Box::leak(Box::new(MemFlash::new(4096, 8)));
+    storage::install(flash).unwrap();
+    assert!(!get("/day/2").text().contains("Re-run saved input"));
+    assert_eq!(post("/saved/2", None, b"").status, 404);
+
+    // Twice over, to tell it apart from the example
+    let example = problems::get(2).unwrap().examples[0].input;
+    let input = format!("{example}\r\n{example}");
+    assert_eq!(post("/day/2?part=1", None, input.as_bytes()).status, 200);
+    // Written once the request's done with, normally by `storage::writer`
+    storage::write_pending();
+    let response = get("/day/2");
+    let text = response.text();
+    assert!(text.contains(r#"<form method="post" action="/saved/2">"#), "{text}");
+    let saved = format!("<p>{} bytes, part 1: <code>4</code></p>", input.len());
+    assert!(text.contains(&saved), "{text}");
+
+    // Same parts as last time, unless asked otherwise
+    let response = post("/saved/2", None, b"");
+    assert_eq!(response.status, 200);
+    assert!(response.text().contains("Part 1: 4<br>"), "{}", response.text());
+    assert!(!response.text().contains("Part 2"));
+    let response = post("/saved/2?part=both&cache=off", None, b"");
+    assert!(response.text().contains("Part 2: 8<br>"), "{}", response.text());
+    // Saving doesn't mean caching
+    assert!(!get("/cache").text().contains("<tr><td>2</td><td>both</td>"));
+    // and re-runs aren't saved again
+    storage::write_pending();
+    assert!(get("/day/2").text().contains(&saved));
+
+    // Nor are the examples, sent the way the input page sends them
+    assert!(get("/day/2").text().contains(r#"<input type="hidden" name="cache" value="off">"#));
+    let form = format!("part=both\r\nexpect1=2\r\nexpect2=4\r\ncache=off\r\nmessage={example}");
+    let response = post("/day/2", Some("text/plain"), form.as_bytes());
+    assert_eq!(response.status, 200, "{}", response.text());
+    storage::write_pending();
+    assert!(get("/day/2").text().contains(&saved));
+
+    assert_eq!(post("/saved/26", None, b"").status, 404);
+}
+
+#[test]
+fn history_page() {
+    let input = problems::get(3).unwrap().examples[0].input;
+    assert_eq!(post("/day/3?part=1&cache=off", None, input.as_bytes()).status, 200);
+    let response = get("/history");
+    assert_eq!(response.status, 200);
+    let text = response.text();
+    let row = format!(r#"<td><a href="/day/3">3</a></td><td>1</td><td>{}</td>"#, input.len());
+    assert!(text.contains(&row), "{text}");
+    assert!(text.contains("<td>161</td><td></td>"), "{text}");
+
+    // The size is the input's, not the form's
+    let form = format!("part=2\r\ncache=off\r\nmessage={input}");
+    assert_eq!(post("/day/3", Some("text/plain"), form.as_bytes()).status, 200);
+    let response = get("/history");
+    let text = response.text();
+    let row = format!(r#"<td><a href="/day/3">3</a></td><td>2</td><td>{}</td>"#, input.len());
+    assert!(text.contains(&row), "{text}");
+
+    assert_eq!(post("/api/day/3", None, b"").status, 400);
+    let response = get("/history");
+    let text = response.text();
+    assert!(text.contains("No puzzle input in the request body</span>"), "{text}");
+    // Days that don't exist have nowhere to link to
+    assert_eq!(post("/api/day/30", None, input.as_bytes()).status, 404);
+    assert!(!get("/history").text().contains(r#"<a href="/day/30">"#));
+    assert!(get("/").text().contains(r#"<a href="/history">"#));
+}
+
+// Other tests are making requests at the same time, so counts can only be checked for being there
+#[test]
+fn metrics() {
+    get("/");
+    assert_eq!(get("/no/such/page").status, 404);
+    assert_eq!(post("/api/day/1?cache=off", None, DAY1_EXAMPLE.as_bytes()).status, 200);
+    let response = get("/metrics");
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Content-Type"), Some("text/plain; version=0.0.4"));
+    let text = response.text();
+    assert!(text.starts_with("# HELP aoc_uptime_seconds "), "{text}");
+    for line in [
+        "# TYPE aoc_heap_peak_bytes gauge\naoc_heap_peak_bytes ",
+        "# TYPE aoc_index_views_total counter\naoc_index_views_total ",
+        "# TYPE aoc_http_requests_total counter\n",
+        r#"aoc_http_requests_total{route="/",status="200"} "#,
+        r#"aoc_http_requests_total{route="other",status="404"} "#,
+        r#"aoc_http_requests_total{route="/api/day/{n}",status="200"} "#,
+        "# TYPE aoc_solve_duration_seconds summary\n",
+        r#"aoc_solve_duration_seconds_count{day="1"} "#,
+        r#"aoc_solve_duration_seconds_sum{day="1"} 0."#,
+    ] {
+        assert!(text.contains(line), "{line} in {text}");
+    }
+    // Every sample is a name, maybe some labels, and a number
+    for sample in text.lines().filter(|line| !line.starts_with('#')) {
+        let (_, value) = sample.rsplit_once(' ').unwrap();
+        assert!(value.parse::<f64>().is_ok(), "{sample}");
+    }
+}
+
+#[test]
+fn oversize_inputs() {
+    let limit = problems::get(1).unwrap().input_limit();
+    let input = DAY1_EXAMPLE.repeat(limit / DAY1_EXAMPLE.len() + 1);
+    let response = post("/day/1", None, input.as_bytes());
+    assert_eq!(response.status, 413);
+    assert!(
+        response.text().contains(&format!("over the {limit} byte limit")),
+        "{}",
+        response.text()
+    );
+
+    let response = post("/api/day/1", None, input.as_bytes());
+    assert_eq!(response.status, 413);
+    assert!(response.text().contains(r#""code":"input_too_large""#));
+
+    // Just under the limit is fine
+    let under = &input[..limit - limit % DAY1_EXAMPLE.len()];
+    let response = post("/day/1", None, under.as_bytes());
+    assert_eq!(response.status, 200);
+
+    // The limit's on the input, not on the form fields or the percent-encoding around it
+    let form = format!("part=both\r\ncache=off\r\nmessage={under}");
+    let response = post("/day/1", Some("text/plain"), form.as_bytes());
+    assert_eq!(response.status, 200, "{}", response.text());
+    let encoded = |input: &str| format!("part=both&message={}", input.replace(' ', "+").replace('\n', "%0A"));
+    let urlencoded = Some("application/x-www-form-urlencoded");
+    let response = post("/day/1", urlencoded, encoded(under).as_bytes());
+    assert_eq!(response.status, 200, "{}", response.text());
+    let response = post("/api/day/1", urlencoded, encoded(&input).as_bytes());
+    assert_eq!(response.status, 413);
+    assert!(response.text().contains(r#""code":"input_too_large""#), "{}", response.text());
+}
+
+#[test]
+fn api() {
+    let response = post("/api/day/1", None, DAY1_EXAMPLE.as_bytes());
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Content-Type"), Some("application/json"));
+    assert!(response.text().starts_with(r#"{"day":1,"part1":11,"part2":31,"elapsed_ms":"#));
+    assert!(response.text().contains(r#","heap":{"before":"#));
+    assert!(response.text().ends_with(r#""error":null}"#));
+
+    let response = post("/api/day/25", None, DAY1_EXAMPLE.as_bytes());
+    assert_eq!(response.status, 501);
+    assert!(response.text().contains(r#""error":{"code":"unimplemented""#), "{}", response.text());
+
+    let response = post("/api/day/30", None, DAY1_EXAMPLE.as_bytes());
+    assert_eq!(response.status, 404);
+    assert!(response.text().contains(r#""error":{"code":"bad_day""#));
+
+    let response = post("/api/day/1", None, b"");
+    assert_eq!(response.status, 400);
+    assert!(response.text().contains(r#""code":"missing_message""#));
+}
@@ -0,0 +1,75 @@
+//! Flash for the simulator to save inputs to, kept in a file so they last between runs.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{ErrorKind, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use aoc_core::storage::{Flash, FlashError, MemFlash};
+
+/// Same layout as the board's storage partition.
+pub const SECTOR_SIZE: usize = 4096;
+pub const SECTORS: usize = 64;
+
+/// [`MemFlash`] with every change written through to a file.
+pub struct FileFlash {
+    mem: MemFlash,
+    file: File,
+}
+
+impl FileFlash {
+    /// Open `path`, or make it a freshly erased flash if it doesn't exist yet or is empty. Anything
+    /// else that isn't the size of a flash image is left alone, in case it's the wrong file.
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        let size = SECTOR_SIZE * SECTORS;
+        let mem = if data.len() == size {
+            MemFlash::from_bytes(SECTOR_SIZE, data)
+        } else if data.is_empty() {
+            let mem = MemFlash::new(SECTOR_SIZE, SECTORS);
+            file.write_all(mem.as_bytes())?;
+            mem
+        } else {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!("{} isn't a flash image, it's {} bytes rather than {size}", path.display(), data.len()),
+            ));
+        };
+        Ok(Self { mem, file })
+    }
+
+    fn write_through(&mut self, offset: usize, len: usize) -> Result<(), FlashError> {
+        let bytes = &self.mem.as_bytes()[offset..offset + len];
+        self.file
+            .seek(SeekFrom::Start(offset as u64))
+            .and_then(|_| self.file.write_all(bytes))
+            .map_err(|_| FlashError::Device)
+    }
+}
+
+impl Flash for FileFlash {
+    fn sector_size(&self) -> usize {
+        self.mem.sector_size()
+    }
+
+    fn sectors(&self) -> usize {
+        self.mem.sectors()
+    }
+
+    fn read(&mut self, offset: usize, buf: &mut [u8]) -> Result<(), FlashError> {
+        self.mem.read(offset, buf)
+    }
+
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), FlashError> {
+        self.mem.write(offset, data)?;
+        self.write_through(offset, data.len())
+    }
+
+    fn erase(&mut self, sector: usize) -> Result<(), FlashError> {
+        self.mem.erase(sector)?;
+        self.write_through(sector * SECTOR_SIZE, SECTOR_SIZE)
+    }
+}
@@ -0,0 +1,116 @@
+//! A global allocator that can be capped, to get an idea of whether a solve will fit into the
+//! device's heap before trying it there.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Wraps the system allocator, keeping track of how much is allocated. Once [`CappedHeap::cap`]
+/// is called, allocations that would take usage over the cap fail, as they would on the device.
+pub struct CappedHeap {
+    used: AtomicUsize,
+    peak: AtomicUsize,
+    limit: AtomicUsize,
+}
+
+impl CappedHeap {
+    pub const fn new() -> Self {
+        Self {
+            used: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+            limit: AtomicUsize::new(usize::MAX),
+        }
+    }
+
+    /// Bytes currently allocated.
+    pub fn used(&self) -> usize {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    /// Highest usage since the last [`CappedHeap::reset_peak`].
+    pub fn peak(&self) -> usize {
+        self.peak.load(Ordering::Relaxed)
+    }
+
+    /// Bytes left before the cap. Effectively unlimited when uncapped.
+    pub fn free(&self) -> usize {
+        self.limit.load(Ordering::Relaxed).saturating_sub(self.used())
+    }
+
+    pub fn reset_peak(&self) {
+        self.peak.store(self.used(), Ordering::Relaxed);
+    }
+
+    /// Allow `limit` more bytes on top of what's allocated right now.
+    pub fn cap(&self, limit: usize) {
+        self.limit.store(self.used().saturating_add(limit), Ordering::Relaxed);
+    }
+
+    pub fn uncap(&self) {
+        self.limit.store(usize::MAX, Ordering::Relaxed);
+    }
+
+    /// Account for `size` more bytes, unless that would go over the limit.
+    fn claim(&self, size: usize) -> bool {
+        let limit = self.limit.load(Ordering::Relaxed);
+        let mut used = self.used.load(Ordering::Relaxed);
+        loop {
+            let Some(total) = used.checked_add(size).filter(|total| *total <= limit) else {
+                return false;
+            };
+            match self
+                .used
+                .compare_exchange_weak(used, total, Ordering::Relaxed, Ordering::Relaxed)
+            {
+                Ok(_) => {
+                    self.peak.fetch_max(total, Ordering::Relaxed);
+                    return true;
+                },
+                Err(actual) => used = actual,
+            }
+        }
+    }
+
+    fn release(&self, size: usize) {
+        self.used.fetch_sub(size, Ordering::Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for CappedHeap {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if !self.claim(layout.size()) {
+            return std::ptr::null_mut();
+        }
+        let ptr = System.alloc(layout);
+        if ptr.is_null() {
+            self.release(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        self.release(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if new_size > layout.size() {
+            if !self.claim(new_size - layout.size()) {
+                return std::ptr::null_mut();
+            }
+        } else {
+            self.release(layout.size() - new_size);
+        }
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if new_ptr.is_null() {
+            // The old allocation is still there, put the accounting back how it was
+            if new_size > layout.size() {
+                self.release(new_size - layout.size());
+            } else {
+                self.used.fetch_add(layout.size() - new_size, Ordering::Relaxed);
+            }
+        }
+        new_ptr
+    }
+}
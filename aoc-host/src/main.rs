@@ -0,0 +1,176 @@
+//! Runs the same solvers as the firmware, on the host. Handy for checking answers and memory usage
+//! without flashing anything.
+
+use std::{io::Write, path::PathBuf, process::ExitCode};
+
+use aoc_core::{
+    helpers::InputFormat,
+    mem::{set_heap_probe, HeapProbe, HeapUsage, PeakTracker},
+    pages::{do_problem, run_problem, SolveOptions},
+    problems::{self, Parts},
+};
+use heap::CappedHeap;
+
+mod heap;
+
+#[global_allocator]
+static HEAP: CappedHeap = CappedHeap::new();
+
+static HEAP_PROBE: HeapProbe = HeapProbe {
+    free: || HEAP.free(),
+    used: || HEAP.used(),
+    peak: Some(PeakTracker {
+        peak: || HEAP.peak(),
+        reset: || HEAP.reset_peak(),
+    }),
+};
+
+const USAGE: &str = "Usage: aoc run --day <N> [--part <PART>] [--heap-limit <SIZE>] [--html] <INPUT>
+
+Options:
+  --day <N>            Day to solve
+  --part <PART>        1, 2 or both. Defaults to both
+  --heap-limit <SIZE>  Fail allocations past SIZE bytes, as the device would. Takes k and m suffixes,
+                       e.g. 72k for the ESP32's heap
+  --html               Print the result page the device would serve, instead of plain text";
+
+struct RunArgs {
+    day: u32,
+    part: Option<Parts>,
+    heap_limit: Option<usize>,
+    html: bool,
+    input: PathBuf,
+}
+
+/// Parse sizes like `72k`, `1m` or `4096`. Suffixes are powers of 1024.
+fn parse_size(s: &str) -> Option<usize> {
+    let (digits, multiplier) = match s.as_bytes().last()? {
+        b'k' | b'K' => (&s[..s.len() - 1], 1024),
+        b'm' | b'M' => (&s[..s.len() - 1], 1024 * 1024),
+        _ => (s, 1),
+    };
+    digits.parse::<usize>().ok()?.checked_mul(multiplier)
+}
+
+fn parse_args(args: &[String]) -> Result<RunArgs, String> {
+    let mut args = args.iter();
+    match args.next().map(String::as_str) {
+        Some("run") => (),
+        Some(other) => return Err(format!("Unknown command {other}")),
+        None => return Err("No command given".into()),
+    }
+    let mut day = None;
+    let mut part = None;
+    let mut heap_limit = None;
+    let mut html = false;
+    let mut input = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--day" => {
+                let value = args.next().ok_or("--day needs a value")?;
+                day = Some(value.parse().map_err(|_| format!("Bad day {value}"))?);
+            },
+            "--part" => {
+                let value = args.next().ok_or("--part needs a value")?;
+                part = Some(value.parse().map_err(|()| format!("Bad part {value}"))?);
+            },
+            "--heap-limit" => {
+                let value = args.next().ok_or("--heap-limit needs a value")?;
+                heap_limit = Some(parse_size(value).ok_or(format!("Bad heap limit {value}"))?);
+            },
+            "--html" => html = true,
+            path if input.is_none() && !path.starts_with("--") => input = Some(PathBuf::from(path)),
+            other => return Err(format!("Unexpected argument {other}")),
+        }
+    }
+    Ok(RunArgs {
+        day: day.ok_or("--day is required")?,
+        part,
+        heap_limit,
+        html,
+        input: input.ok_or("No input file given")?,
+    })
+}
+
+fn run(args: &RunArgs) -> ExitCode {
+    let input = match std::fs::read(&args.input) {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("Couldn't read {}: {e}", args.input.display());
+            return ExitCode::FAILURE;
+        },
+    };
+    let entry = match problems::get(args.day) {
+        Ok(entry) => entry,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        },
+    };
+
+    if let Some(limit) = args.heap_limit {
+        eprintln!("Limiting the solver to {limit} bytes of heap");
+        HEAP.cap(limit);
+    }
+
+    let options = SolveOptions { part: args.part, ..SolveOptions::default() };
+    let succeeded = if args.html {
+        let page = embassy_futures::block_on(do_problem(
+            input.as_slice(),
+            InputFormat::Raw,
+            args.day,
+            input.len(),
+            options,
+        ));
+        HEAP.uncap();
+        match page {
+            Ok(page) => std::io::stdout().write_all(page.as_bytes()).is_ok(),
+            Err(e) => {
+                eprintln!("Error when rendering the page: {e}");
+                false
+            },
+        }
+    } else {
+        println!("Day {}: {}", entry.day, entry.title);
+        let run = embassy_futures::block_on(run_problem(
+            input.as_slice(),
+            InputFormat::Raw,
+            args.day,
+            input.len(),
+            options,
+        ));
+        HEAP.uncap();
+        match &run.result {
+            Ok(answer) => print!("{answer}"),
+            Err(e) => println!("Encountered error: {e}"),
+        }
+        println!("Evaluated in {}ms", run.elapsed.as_millis());
+        for (phase, time) in run.timings.phases() {
+            println!("  {:<10} {:>8}µs", phase.name(), time.as_micros());
+        }
+        let HeapUsage { before, peak, after } = run.heap;
+        println!(
+            "Heap used: {before} bytes before, {peak} at peak ({} for the solve), {after} after",
+            peak - before
+        );
+        run.result.is_ok()
+    };
+
+    if succeeded {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn main() -> ExitCode {
+    set_heap_probe(&HEAP_PROBE);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match parse_args(&args) {
+        Ok(args) => run(&args),
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            ExitCode::FAILURE
+        },
+    }
+}
@@ -0,0 +1,111 @@
+//! Runs picoserve over tokio's sockets. picoserve has this built in behind its `tokio` feature, but
+//! turning that on swaps out the runtime-agnostic `serve` the router tests use, for every crate in
+//! the build. So the simulator brings its own.
+
+use std::{fmt, future::Future, io, time::Duration};
+
+use picoserve::{
+    io::{Error, ErrorKind, ErrorType, Read, Socket, Write},
+    Timeouts, Timer,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{
+        tcp::{ReadHalf, WriteHalf},
+        TcpStream,
+    },
+};
+
+#[derive(Debug)]
+pub struct IoError(pub io::Error);
+
+impl fmt::Display for IoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Error for IoError {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+/// One half of a [`Connection`].
+pub struct Half<T>(T);
+
+impl<T> ErrorType for Half<T> {
+    type Error = IoError;
+}
+
+impl Read for Half<ReadHalf<'_>> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, IoError> {
+        self.0.read(buf).await.map_err(IoError)
+    }
+}
+
+impl Write for Half<WriteHalf<'_>> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, IoError> {
+        self.0.write(buf).await.map_err(IoError)
+    }
+}
+
+/// An accepted connection, for `picoserve::serve`.
+pub struct Connection(pub TcpStream);
+
+impl Socket for Connection {
+    type Error = IoError;
+    type ReadHalf<'a> = Half<ReadHalf<'a>>;
+    type WriteHalf<'a> = Half<WriteHalf<'a>>;
+
+    fn split(&mut self) -> (Self::ReadHalf<'_>, Self::WriteHalf<'_>) {
+        let (read, write) = self.0.split();
+        (Half(read), Half(write))
+    }
+
+    /// Close our side, then wait for the client to close theirs, as picoserve's own version does.
+    async fn shutdown<T: Timer>(
+        mut self,
+        timeouts: &Timeouts<T::Duration>,
+        timer: &mut T,
+    ) -> Result<(), picoserve::Error<IoError>> {
+        let close = self.0.shutdown();
+        let closed = match timeouts.write.clone() {
+            Some(timeout) => timer.run_with_timeout(timeout, close).await,
+            None => Ok(close.await),
+        };
+        closed
+            .map_err(|_| picoserve::Error::WriteTimeout)?
+            .map_err(|e| picoserve::Error::Write(IoError(e)))?;
+
+        let mut buf = [0; 128];
+        loop {
+            let read = self.0.read(&mut buf);
+            let read = match timeouts.read_request.clone() {
+                Some(timeout) => timer.run_with_timeout(timeout, read).await,
+                None => Ok(read.await),
+            };
+            match read.map_err(|_| picoserve::Error::ReadTimeout)? {
+                Ok(0) => return Ok(()),
+                Ok(_) => {},
+                Err(e) => return Err(picoserve::Error::Read(IoError(e))),
+            }
+        }
+    }
+}
+
+/// Timeouts on tokio's clock.
+pub struct TokioTimer;
+
+impl Timer for TokioTimer {
+    type Duration = Duration;
+    type TimeoutError = tokio::time::error::Elapsed;
+
+    async fn run_with_timeout<F: Future>(
+        &mut self,
+        duration: Duration,
+        future: F,
+    ) -> Result<F::Output, Self::TimeoutError> {
+        tokio::time::timeout(duration, future).await
+    }
+}
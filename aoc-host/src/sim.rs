@@ -0,0 +1,135 @@
+//! Serves the firmware's web app from the host, over an ordinary TCP socket. Same router, same
+//! timeouts and buffer sizes, so page and routing changes can be tried out without the board.
+
+use std::{net::Ipv4Addr, path::PathBuf, process::ExitCode, rc::Rc, sync::Arc, time::Duration};
+
+use aoc_core::{
+    app::{
+        make_app, make_config, HTTP_BUFFER_SIZE, HTTP_SERVER_TASKS, TCP_RX_BUFFER_SIZE,
+        TCP_TX_BUFFER_SIZE,
+    },
+    selftest,
+    storage::{self, Flash, MemFlash},
+};
+use flash::{FileFlash, SECTORS, SECTOR_SIZE};
+use net::{Connection, TokioTimer};
+use tokio::{net::TcpSocket, sync::Semaphore, task::LocalSet};
+
+mod flash;
+mod net;
+
+const USAGE: &str = "Usage: aoc-sim [--port <PORT>] [--flash <FILE>]
+
+Options:
+  --port <PORT>   Port to listen on, defaults to 8080
+  --flash <FILE>  Save inputs to FILE, so they're still there next time. Without it they're only
+                  kept in memory";
+
+struct Args {
+    port: u16,
+    flash: Option<PathBuf>,
+}
+
+fn parse_args(args: &[String]) -> Result<Args, String> {
+    let mut parsed = Args { port: 8080, flash: None };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => {
+                let value = args.next().ok_or("--port needs a value")?;
+                parsed.port = value.parse().map_err(|_| format!("Bad port {value}"))?;
+            },
+            "--flash" => {
+                parsed.flash = Some(args.next().ok_or("--flash needs a file")?.into());
+            },
+            other => return Err(format!("Unexpected argument {other}")),
+        }
+    }
+    Ok(parsed)
+}
+
+/// Set up the storage the firmware keeps in its flash partition.
+fn install_storage(path: Option<&PathBuf>) -> std::io::Result<()> {
+    let flash: Box<dyn Flash + Send> = match path {
+        Some(path) => Box::new(FileFlash::open(path)?),
+        None => Box::new(MemFlash::new(SECTOR_SIZE, SECTORS)),
+    };
+    match storage::install(Box::leak(flash)) {
+        Ok(days) => println!("Storage mounted, {days} days have saved inputs"),
+        Err(e) => println!("Couldn't mount storage, nothing will be saved: {e}"),
+    }
+    Ok(())
+}
+
+/// Listening socket with the device's TCP buffer sizes. Accepted connections inherit them.
+/// Linux doubles the requested sizes and has minimums of its own, so this is as close as we get.
+// The sizes are all small constants
+#[allow(clippy::cast_possible_truncation)]
+fn listen(port: u16) -> std::io::Result<tokio::net::TcpListener> {
+    let socket = TcpSocket::new_v4()?;
+    socket.set_reuseaddr(true)?;
+    socket.set_recv_buffer_size(TCP_RX_BUFFER_SIZE as u32)?;
+    socket.set_send_buffer_size(TCP_TX_BUFFER_SIZE as u32)?;
+    socket.bind((Ipv4Addr::UNSPECIFIED, port).into())?;
+    socket.listen(HTTP_SERVER_TASKS as u32)
+}
+
+async fn serve(port: u16) -> std::io::Result<()> {
+    let listener = listen(port)?;
+    // As the firmware does at boot
+    match selftest::run().await {
+        0 => println!("Self-test passed"),
+        broken => println!("Self-test failed, {broken} days broken, see /selftest"),
+    }
+    // Flash writes happen off to the side, as on the device
+    tokio::task::spawn_local(storage::writer());
+    let app = Rc::new(make_app());
+    let config = Rc::new(make_config(Duration::from_secs));
+    // The device has one task per connection, and new connections wait until a task is free
+    let tasks = Arc::new(Semaphore::new(HTTP_SERVER_TASKS));
+    println!("Serving on http://localhost:{port}/");
+
+    loop {
+        let permit = tasks.clone().acquire_owned().await.expect("the semaphore is never closed");
+        let (stream, remote) = listener.accept().await?;
+        let app = app.clone();
+        let config = config.clone();
+        tokio::task::spawn_local(async move {
+            let mut http_buffer = [0; HTTP_BUFFER_SIZE];
+            let served =
+                picoserve::serve(&app, TokioTimer, &config, &mut http_buffer, Connection(stream)).await;
+            match served {
+                Ok(requests) => println!("{requests} requests handled from {remote}"),
+                Err(e) => println!("Error serving {remote}: {e:?}"),
+            }
+            drop(permit);
+        });
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Args { port, flash } = match parse_args(&args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        },
+    };
+    if let Err(e) = install_storage(flash.as_ref()) {
+        eprintln!("Couldn't open the flash file: {e}");
+        return ExitCode::FAILURE;
+    }
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("couldn't start the tokio runtime");
+    // picoserve's connections aren't Send, so everything runs on the one thread, much like the device
+    match runtime.block_on(LocalSet::new().run_until(serve(port))) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Server stopped: {e}");
+            ExitCode::FAILURE
+        },
+    }
+}
@@ -1,3 +1,3 @@
 pub const WIFI_SSID: &str = env!("WIFI_SSID");
 pub const WIFI_PASSWORD: &str = env!("WIFI_PASSWORD");
-pub const HTTP_SERVER_TASKS: usize = 1;
+pub use aoc_core::app::HTTP_SERVER_TASKS;
@@ -1,6 +1,7 @@
 #![no_std]
 #![no_main]
 
+use aoc_core::metrics::{self, Counter, Gauge};
 use defmt::{error, info};
 use embassy_executor::Spawner;
 use embassy_net::{DhcpConfig, Stack, StackResources};
@@ -25,16 +26,12 @@ use esp_wifi::{
     },
     EspWifiController,
 };
+use esp_wifi_sys::include::{esp_wifi_sta_get_ap_info, wifi_ap_record_t};
 
 mod consts;
-mod error;
-mod pages;
-mod problems;
 mod server;
-mod helpers;
+mod storage;
 pub use consts::*;
-pub use error::Result;
-extern crate alloc;
 
 // this hack is lifted straight from the example projects
 // When you are okay with using a nightly compiler it's better to use https://docs.rs/static_cell/2.1.0/static_cell/macro.make_static.html
@@ -47,6 +44,39 @@ macro_rules! mk_static {
     }};
 }
 
+static WIFI_RECONNECTS: Counter =
+    Counter::new("aoc_wifi_reconnects_total", "Times Wi-Fi has connected again after dropping out.");
+static WIFI_RSSI: Gauge =
+    Gauge::new("aoc_wifi_rssi_dbm", "Signal strength of the access point, when connected.");
+
+/// esp-alloc doesn't keep a high-water mark, so the peak is sampled by `aoc_core::mem` instead.
+static HEAP_PROBE: aoc_core::mem::HeapProbe = aoc_core::mem::HeapProbe {
+    free: || esp_alloc::HEAP.free(),
+    used: || esp_alloc::HEAP.used(),
+    peak: None,
+};
+
+extern "C" {
+    /// The stack protector's canary, which esp-hal's linker script puts 4 KiB up from the bottom of
+    /// the stack, and esp-hal fills in at start up. Painting over it would make every function
+    /// with a protected frame panic on return.
+    static __stack_chk_guard: u32;
+}
+
+/// The stack grows down towards `_stack_end`, but only the part above the canary is painted, so
+/// the stack figures are blind to the 4 KiB below it and `free` comes out that much short.
+static STACK_PROBE: aoc_core::stack::StackProbe = aoc_core::stack::StackProbe {
+    limit: || (&raw const __stack_chk_guard) as usize + size_of::<u32>(),
+    pointer: stack_pointer,
+};
+
+fn stack_pointer() -> usize {
+    let sp: usize;
+    // SAFETY: only reads a register
+    unsafe { core::arch::asm!("mv {}, sp", out(reg) sp, options(nomem, nostack, preserves_flags)) };
+    sp
+}
+
 #[esp_hal_embassy::main]
 async fn main(spawner: Spawner) -> ! {
     let peripherals = esp_hal::init({
@@ -56,6 +86,19 @@ async fn main(spawner: Spawner) -> ! {
     });
 
     esp_alloc::heap_allocator!(72 * 1024);
+    aoc_core::mem::set_heap_probe(&HEAP_PROBE);
+    aoc_core::stack::set_stack_probe(&STACK_PROBE);
+
+    // Inputs saved before the last reboot. Solving still works without them
+    match storage::EspFlash::new() {
+        Some(flash) => {
+            let flash = mk_static!(storage::EspFlash, flash);
+            if let Err(e) = aoc_core::storage::install(flash) {
+                error!("Couldn't mount flash storage, inputs won't be saved: {:?}", e);
+            }
+        },
+        None => error!("No flash storage, inputs won't be saved. Was it flashed with partitions.csv?"),
+    }
 
     let timg0 = TimerGroup::new(peripherals.TIMG0);
 
@@ -93,6 +136,7 @@ async fn main(spawner: Spawner) -> ! {
 
     spawner.must_spawn(connection(controller));
     spawner.must_spawn(net_task(stack));
+    spawner.must_spawn(storage_writer());
 
     loop {
         if stack.is_link_up() {
@@ -110,18 +154,43 @@ async fn main(spawner: Spawner) -> ! {
         Timer::after(Duration::from_millis(500)).await;
     }
 
+    // Before serving anything, so the pages never show a day as untested
+    aoc_core::selftest::run().await;
+
+    // These only count things that go wrong, register them so they show up as 0 until then
+    metrics::register(&WIFI_RECONNECTS);
+    metrics::register(&WIFI_RSSI);
+    metrics::register(&server::ACCEPT_FAILURES);
+
     for id in 0..HTTP_SERVER_TASKS {
         spawner.must_spawn(server::serve(id, stack));
     }
     loop {
+        if let Some(rssi) = rssi() {
+            WIFI_RSSI.set(i32::from(rssi));
+        }
         Timer::after(Duration::from_millis(10000)).await;
     }
 }
 
+/// Signal strength of the access point we're connected to, in dBm.
+fn rssi() -> Option<i8> {
+    if esp_wifi::wifi::wifi_state() != WifiState::StaConnected {
+        return None;
+    }
+    // SAFETY: the record is plain data, all zeroes is a valid one, and it's only read once the
+    // driver has filled it in
+    unsafe {
+        let mut record: wifi_ap_record_t = core::mem::zeroed();
+        (esp_wifi_sta_get_ap_info(&mut record) == 0).then_some(record.rssi)
+    }
+}
+
 #[embassy_executor::task]
 async fn connection(mut controller: WifiController<'static>) {
     info!("start connection task");
     // debug!("Device capabilities: {:?}", controller.capabilities());
+    let mut connected_before = false;
     loop {
         if esp_wifi::wifi::wifi_state() == WifiState::StaConnected {
             // wait until we're no longer connected
@@ -144,7 +213,13 @@ async fn connection(mut controller: WifiController<'static>) {
         info!("About to connect...");
 
         match controller.connect_async().await {
-            Ok(()) => info!("Wifi connected!"),
+            Ok(()) => {
+                info!("Wifi connected!");
+                if connected_before {
+                    WIFI_RECONNECTS.inc();
+                }
+                connected_before = true;
+            },
             Err(e) => {
                 error!("Failed to connect to wifi: {:?}", e);
                 Timer::after(Duration::from_millis(5000)).await;
@@ -153,6 +228,12 @@ async fn connection(mut controller: WifiController<'static>) {
     }
 }
 
+/// Writes saved inputs to flash, so the requests that solved them don't have to wait for it.
+#[embassy_executor::task]
+async fn storage_writer() {
+    aoc_core::storage::writer().await;
+}
+
 #[embassy_executor::task]
 async fn net_task(stack: &'static Stack<WifiDevice<'static, WifiStaDevice>>) {
     stack.run().await;
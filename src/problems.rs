@@ -1,5 +0,0 @@
-//! Just the top level module for the problem sources.
-
-pub mod p01;
-pub mod p02;
-pub mod p03;
\ No newline at end of file
@@ -0,0 +1,103 @@
+//! The `aoc` partition of the board's own flash, for `aoc_core::storage` to save inputs in.
+//!
+//! The partition is looked up in the partition table that was flashed along with the firmware, so
+//! `partitions.csv` is the only place that says where it is.
+
+use aoc_core::storage::{Flash, FlashError};
+use defmt::{info, warn};
+use embedded_storage::{nor_flash::NorFlash, ReadStorage};
+use esp_storage::FlashStorage;
+
+/// Where the bootloader looks for the partition table, and the most room it can take up.
+const PARTITION_TABLE: u32 = 0x8000;
+const PARTITION_TABLE_SIZE: u32 = 0xC00;
+/// Every entry in the table is this long, and starts with the magic number.
+const ENTRY_SIZE: usize = 32;
+const ENTRY_MAGIC: [u8; 2] = [0xAA, 0x50];
+
+/// How the partition is listed in `partitions.csv`.
+const PARTITION_TYPE: u8 = 0x01; // data
+const PARTITION_SUBTYPE: u8 = 0x40;
+const PARTITION_LABEL: &[u8] = b"aoc";
+
+pub struct EspFlash {
+    flash: FlashStorage,
+    /// Where the partition starts on the chip, and how big it is.
+    offset: u32,
+    size: u32,
+}
+
+impl EspFlash {
+    /// Find the partition. `None` if the partition table doesn't have it, or it isn't made of
+    /// whole sectors.
+    pub fn new() -> Option<Self> {
+        let mut flash = FlashStorage::new();
+        let (offset, size) = find_partition(&mut flash)?;
+        let sector = FlashStorage::ERASE_SIZE;
+        if offset as usize % sector != 0 || size as usize % sector != 0 || size == 0 {
+            warn!("The aoc partition at {:#x}, {} bytes, isn't made of whole sectors", offset, size);
+            return None;
+        }
+        info!("Found the aoc partition at {:#x}, {} bytes", offset, size);
+        Some(Self { flash, offset, size })
+    }
+
+    /// Where `len` bytes from `offset` into the partition are on the chip.
+    fn address(&self, offset: usize, len: usize) -> Result<u32, FlashError> {
+        let offset = u32::try_from(offset).map_err(|_| FlashError::OutOfBounds)?;
+        let len = u32::try_from(len).map_err(|_| FlashError::OutOfBounds)?;
+        match offset.checked_add(len) {
+            Some(end) if end <= self.size => Ok(self.offset + offset),
+            _ => Err(FlashError::OutOfBounds),
+        }
+    }
+}
+
+/// Look through the partition table for ours. The table ends at the first entry without the magic
+/// number, which is either its checksum or blank flash.
+fn find_partition(flash: &mut FlashStorage) -> Option<(u32, u32)> {
+    let mut entry = [0; ENTRY_SIZE];
+    for address in (PARTITION_TABLE..PARTITION_TABLE + PARTITION_TABLE_SIZE).step_by(ENTRY_SIZE) {
+        ReadStorage::read(flash, address, &mut entry).ok()?;
+        if entry[..2] != ENTRY_MAGIC {
+            return None;
+        }
+        // Type, subtype, offset, size, then the label, padded out with NULs
+        let label = &entry[12..28];
+        let label = &label[..label.iter().position(|b| *b == 0).unwrap_or(label.len())];
+        if entry[2] == PARTITION_TYPE && entry[3] == PARTITION_SUBTYPE && label == PARTITION_LABEL {
+            let offset = u32::from_le_bytes([entry[4], entry[5], entry[6], entry[7]]);
+            let size = u32::from_le_bytes([entry[8], entry[9], entry[10], entry[11]]);
+            return Some((offset, size));
+        }
+    }
+    None
+}
+
+impl Flash for EspFlash {
+    fn sector_size(&self) -> usize {
+        FlashStorage::ERASE_SIZE
+    }
+
+    fn sectors(&self) -> usize {
+        self.size as usize / FlashStorage::ERASE_SIZE
+    }
+
+    // Goes through `ReadStorage`, which copes with reads that aren't lined up with words
+    fn read(&mut self, offset: usize, buf: &mut [u8]) -> Result<(), FlashError> {
+        let address = self.address(offset, buf.len())?;
+        ReadStorage::read(&mut self.flash, address, buf).map_err(|_| FlashError::Device)
+    }
+
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), FlashError> {
+        let address = self.address(offset, data.len())?;
+        NorFlash::write(&mut self.flash, address, data).map_err(|_| FlashError::Device)
+    }
+
+    fn erase(&mut self, sector: usize) -> Result<(), FlashError> {
+        let size = self.sector_size();
+        let from = self.address(sector * size, size)?;
+        let to = self.address((sector + 1) * size, 0)?;
+        NorFlash::erase(&mut self.flash, from, to).map_err(|_| FlashError::Device)
+    }
+}